@@ -28,6 +28,7 @@ chords["<CMD>rs"] = function() set_ruler(cursor(),selection()) end
 chords["<CMD>rg"] = bind_args(scale_ruler,0.5)
 chords["<CMD>rt"] = bind_args(scale_ruler,2)
 chords["<CMD>rx"] = bind_args(scale_ruler,0.0)
+chords["<CMD>rz"] = function() zero_snap() end
 chords["zc"] = function(x,y,w,h) toggle_over(y) end
 
 copy_buffer = nil
@@ -43,7 +44,8 @@ chords["<CMD>v"] = function(x,y,w,h)
 end
 
 chords["<CMD>z"] = undo
-chords["<BKSP>"] = delete
+chords["<CMD><SHFT>z"] = redo
+chords["<BKSP>"] = function() delete() end
 
 click_modes["<SHFT>"] = function(x,y,w,h)
   local cursor_pt = cursor() or 0.0
@@ -42,9 +42,9 @@ impl Player {
     for i in 0..out.len() {
       if i <  channels {
         let idx = floor as usize;
-        let left = self.snd.channel(i).and_then(|s|s.get_sample(&idx)).unwrap_or(&0.0);
-        let right = self.snd.channel(i).and_then(|s|s.get_sample(&(idx+1))).unwrap_or(&0.0);
-        out[i] = lerp(*left,*right,fract);
+        let left = self.snd.channel(i).and_then(|s|s.get_sample(&idx)).unwrap_or(0.0);
+        let right = self.snd.channel(i).and_then(|s|s.get_sample(&(idx+1))).unwrap_or(0.0);
+        out[i] = lerp(left,right,fract);
       }
       else {
         out[i] = out[channels-1];
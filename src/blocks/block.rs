@@ -1,13 +1,17 @@
 mod arc_block;
 mod silent_block;
+mod disk_block;
 
 use arc_block::ArcBlock;
 use silent_block::SilentBlock;
+use disk_block::DiskBlock;
+pub use disk_block::DiskLayout;
 
 #[derive(Clone)]
 pub enum Block {
   Arc(ArcBlock),
-  Silent(SilentBlock)
+  Silent(SilentBlock),
+  Disk(DiskBlock)
 }
 
 impl Block {
@@ -21,33 +25,52 @@ impl Block {
     Self::Silent(sb)
   }
 
+  //maps the file instead of reading it, one block per channel
+  pub fn disk(file:&std::fs::File,layout:DiskLayout) -> std::io::Result<Vec<Block>> {
+    let dbs = DiskBlock::open(file,layout)?;
+    Ok(dbs.into_iter().map(Self::Disk).collect())
+  }
+
   //THIS IS THE "interface" that different kinds of blocks
   //need to use
   pub fn len(&self) -> usize {
     match self {
       Block::Arc(ab) => ab.len(),
-      Block::Silent(sb) => sb.len()
+      Block::Silent(sb) => sb.len(),
+      Block::Disk(db) => db.len()
     }
   }
 
   pub fn rng<R:std::ops::RangeBounds<usize>>(&self,r:R) -> Self {
     match self {
       Block::Arc(ab) => Block::Arc(ab.rng(r)),
-      Block::Silent(sb) => Block::Silent(sb.rng(r))
+      Block::Silent(sb) => Block::Silent(sb.rng(r)),
+      Block::Disk(db) => Block::Disk(db.rng(r))
     }
   }
 
-  pub fn get_sample(&self,index:usize) -> Option<&f32> {
+  pub fn get_sample(&self,index:usize) -> Option<f32> {
     match self {
       Block::Arc(ab) => ab.get_sample(index),
-      Block::Silent(sb) => sb.get_sample(index)
+      Block::Silent(sb) => sb.get_sample(index),
+      Block::Disk(db) => db.get_sample(index)
     }
   }
 
   pub fn summary(&self) -> (f32,f32) {
     match self {
       Block::Arc(ab) => ab.summary(),
-      Block::Silent(sb) => sb.summary()
+      Block::Silent(sb) => sb.summary(),
+      Block::Disk(db) => db.summary()
+    }
+  }
+
+  //silence doesn't take up any room
+  pub fn storage(&self) -> Option<(usize,usize)> {
+    match self {
+      Block::Arc(ab) => Some(ab.storage()),
+      Block::Silent(_) => None,
+      Block::Disk(db) => Some(db.storage())
     }
   }
 
@@ -95,7 +118,7 @@ impl Iterator for BlockRunner {
 
     let out = self.b.get_sample(self.start);
     self.start += 1;
-    out.copied()
+    out
   }
 }
 
@@ -108,7 +131,7 @@ impl DoubleEndedIterator for BlockRunner {
      return None
     }
 
-    self.b.get_sample(self.end).copied()
+    self.b.get_sample(self.end)
   }
 }
 
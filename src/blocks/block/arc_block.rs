@@ -42,7 +42,7 @@ impl ArcBlock {
     }
   }
   
-  pub fn get_sample(&self,index:usize) -> Option<&f32> {
+  pub fn get_sample(&self,index:usize) -> Option<f32> {
     let sample_index = self.start + index;
 
     if sample_index >= self.end {
@@ -50,10 +50,17 @@ impl ArcBlock {
     }
     else {
       //we shouldn't have to check bounds
-      Some(&self.data[sample_index])
+      Some(self.data[sample_index])
     }
   }
 
+  //where the samples live and how much memory they hold onto,
+  //blocks cut from the same data all point at the same place
+  pub fn storage(&self) -> (usize,usize) {
+    let at = Arc::as_ptr(&self.data) as *const f32 as usize;
+    (at,self.data.len() * std::mem::size_of::<f32>() + self.mips.bytes())
+  }
+
   pub fn summary(&self) -> (f32,f32) {
     self.mips.peaks(self.start,self.end,&self.data[..])
   }
@@ -74,12 +81,12 @@ mod tests {
     let sub1 = block.rng(10..50);
     assert_eq!(sub1.len(),40,"cutting arc block: sub block length");
     assert_eq!(sub1.summary(),(10.0,49.0),"cutting arc block: sub block summary");
-    assert_eq!(sub1.get_sample(4),Some(&14.0),"cutting arc block: sub block sample");
+    assert_eq!(sub1.get_sample(4),Some(14.0),"cutting arc block: sub block sample");
 
     let sub2 = sub1.rng(10..20);
     assert_eq!(sub2.len(),10,"cutting arc block: sub block length");
     assert_eq!(sub2.summary(),(20.0,29.0),"cutting arc block: sub block summary");
-    assert_eq!(sub2.get_sample(4),Some(&24.0),"cutting arc block: sub block sample");
+    assert_eq!(sub2.get_sample(4),Some(24.0),"cutting arc block: sub block sample");
     assert_eq!(sub2.get_sample(11),None,"cutting arc block: sub block sample out of range");
   }
 }
@@ -0,0 +1,224 @@
+use std::sync::Arc;
+use crate::blocks::mips::Pyramid;
+use crate::util::range_bounds;
+
+//where the samples live in a file, frames are interleaved
+//and start at offset
+#[derive(Debug,Clone,Copy)]
+pub struct DiskLayout {
+  pub offset:usize,
+  pub frames:usize,
+  pub channels:usize,
+  pub width:usize,
+  pub float:bool,
+  pub big_endian:bool
+}
+
+//the file gets mapped once and every channel's blocks share it.
+//if something else changes the file while it's mapped all bets are off,
+//which is why saving writes a new file and swaps it in
+struct Source {
+  map:memmap2::Mmap,
+  layout:DiskLayout
+}
+
+impl Source {
+  fn sample(&self,chan:usize,frame:usize) -> f32 {
+    let l = &self.layout;
+    let at = l.offset + (frame*l.channels + chan)*l.width;
+    decode(&self.map[at..at+l.width],l)
+  }
+}
+
+//ints get pushed to the top of an i32 so they all scale the same
+fn decode(b:&[u8],l:&DiskLayout) -> f32 {
+  use dasp::Sample;
+
+  match (l.float,l.big_endian) {
+    (true,true) => f32::from_be_bytes([b[0],b[1],b[2],b[3]]),
+    (true,false) => f32::from_le_bytes([b[0],b[1],b[2],b[3]]),
+    (false,true) => {
+      let v = b.iter().fold(0i32,|v,byte|(v << 8) | *byte as i32);
+      (v << (32 - 8*b.len())).to_sample::<f32>()
+    },
+    (false,false) => {
+      let v = b.iter().rev().fold(0i32,|v,byte|(v << 8) | *byte as i32);
+      (v << (32 - 8*b.len())).to_sample::<f32>()
+    }
+  }
+}
+
+//a summary per page instead of per pair of samples keeps
+//the summary data tiny compared to the file
+const PAGE : usize = 256;
+
+struct Summary {
+  pages:Vec<(f32,f32)>,
+  mips:Pyramid
+}
+
+fn scan(src:&Source,chan:usize,start:usize,end:usize) -> (f32,f32) {
+  (start..end).fold((f32::MAX,f32::MIN),|(min,max),i|{
+    let s = src.sample(chan,i);
+    (min.min(s),max.max(s))
+  })
+}
+
+//one pass over the file to summarize every channel, split up across
+//threads since it's the slow part of opening a big file
+fn summarize(src:&Source) -> Vec<Summary> {
+  let l = &src.layout;
+  let page_count = l.frames.div_ceil(PAGE);
+  let threads = std::thread::available_parallelism().map(|n|n.get()).unwrap_or(1);
+  let per_thread = page_count.div_ceil(threads).max(1);
+
+  let parts : Vec<Vec<Vec<(f32,f32)>>> = std::thread::scope(|s|{
+    let handles : Vec<_> = (0..page_count).step_by(per_thread).map(|first|{
+      s.spawn(move||{
+        let last = (first + per_thread).min(page_count);
+
+        (0..l.channels).map(|c|{
+          (first..last).map(|p|scan(src,c,p*PAGE,((p+1)*PAGE).min(l.frames))).collect()
+        }).collect()
+      })
+    }).collect();
+
+    handles.into_iter().map(|h|h.join().unwrap()).collect()
+  });
+
+  (0..l.channels).map(|c|{
+    let pages : Vec<(f32,f32)> = parts.iter().flat_map(|p|p[c].iter().copied()).collect();
+    let mips = Pyramid::pairs(&pages[..]);
+    Summary{ pages, mips }
+  }).collect()
+}
+
+#[derive(Clone)]
+pub struct DiskBlock {
+  src:Arc<Source>,
+  summary:Arc<Summary>,
+  chan:usize,
+  start:usize,
+  end:usize
+}
+
+impl DiskBlock {
+  //one block per channel
+  pub fn open(file:&std::fs::File,layout:DiskLayout) -> std::io::Result<Vec<Self>> {
+    //8 bit wavs are unsigned and decode doesn't know that, and zero width
+    //samples don't make any sense at all
+    if !(2..=4).contains(&layout.width) || layout.channels == 0 {
+      let msg = format!("can't read {} byte samples in {} channels off disk",layout.width,layout.channels);
+      return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput,msg));
+    }
+
+    // SAFETY: the map is only ever read, and we never write to a file that's
+    // mapped, saving goes to a new file that gets renamed over the old one so
+    // the mapped inode stays as it was. nothing stops another program from
+    // truncating it though, and reading past the new end would fault
+    let map = unsafe { memmap2::Mmap::map(file)? };
+
+    //a file that got cut off short just gets less frames
+    let avail = map.len().saturating_sub(layout.offset) / (layout.width * layout.channels).max(1);
+    let layout = DiskLayout{ frames:layout.frames.min(avail), ..layout };
+
+    let src = Arc::new(Source{ map, layout });
+    let summaries = summarize(&src);
+
+    Ok(summaries.into_iter().enumerate().map(|(chan,summary)|{
+      Self {
+        src:src.clone(),
+        summary:Arc::new(summary),
+        chan,
+        start:0,
+        end:layout.frames
+      }
+    }).collect())
+  }
+
+  pub fn len(&self) -> usize { self.end - self.start }
+
+  pub fn rng<R:std::ops::RangeBounds<usize>>(&self,r:R) -> Self {
+    let (start,end) = range_bounds(r,self.end);
+
+    let s = self.start + start;
+    let e = self.start + end;
+
+    Self {
+      src:self.src.clone(),
+      summary:self.summary.clone(),
+      chan:self.chan,
+      start:s.clamp(self.start,self.end),
+      end:e.clamp(self.start,self.end)
+    }
+  }
+
+  pub fn get_sample(&self,index:usize) -> Option<f32> {
+    let sample_index = self.start + index;
+
+    if sample_index >= self.end {
+      None
+    }
+    else {
+      Some(self.src.sample(self.chan,sample_index))
+    }
+  }
+
+  //the samples are the file's problem, only the summary counts
+  pub fn storage(&self) -> (usize,usize) {
+    let at = Arc::as_ptr(&self.summary) as usize;
+    (at,self.summary.pages.len() * std::mem::size_of::<(f32,f32)>() + self.summary.mips.bytes())
+  }
+
+  //whole pages come out of the summary, the ragged ends get read
+  pub fn summary(&self) -> (f32,f32) {
+    let (s,e) = (self.start,self.end);
+    let first_page = s.div_ceil(PAGE);
+    let last_page = e / PAGE;
+
+    if first_page >= last_page {
+      return scan(&self.src,self.chan,s,e);
+    }
+
+    let (hmin,hmax) = scan(&self.src,self.chan,s,first_page*PAGE);
+    let (pmin,pmax) = self.summary.mips.pair_peaks(first_page,last_page,&self.summary.pages[..]);
+    let (tmin,tmax) = scan(&self.src,self.chan,last_page*PAGE,e);
+
+    (hmin.min(pmin).min(tmin),hmax.max(pmax).max(tmax))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_creation() {
+    let path = std::env::temp_dir().join("ksnd_disk_block.raw");
+    let bytes : Vec<u8> = (0..3000i16).flat_map(|s|[s,-s]).flat_map(|s|s.to_le_bytes()).collect();
+    std::fs::write(&path,&bytes).unwrap();
+
+    let layout = DiskLayout{ offset:0, frames:3000, channels:2, width:2, float:false, big_endian:false };
+    let file = std::fs::File::open(&path).unwrap();
+    let blocks = DiskBlock::open(&file,layout).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    let scale = 1.0/32768.0;
+    assert_eq!(blocks.len(),2,"disk block: one block per channel");
+    assert_eq!(blocks[0].len(),3000,"disk block: the length should be correct");
+    assert_eq!(blocks[0].summary(),(0.0,2999.0*scale),"disk block: the summary should be correct");
+    assert_eq!(blocks[1].get_sample(10),Some(-10.0*scale),"disk block: the second channel");
+
+    let sub1 = blocks[0].rng(100..2000);
+    assert_eq!(sub1.len(),1900,"cutting disk block: sub block length");
+    assert_eq!(sub1.summary(),(100.0*scale,1999.0*scale),"cutting disk block: sub block summary");
+    assert_eq!(sub1.get_sample(4),Some(104.0*scale),"cutting disk block: sub block sample");
+
+    let sub2 = sub1.rng(10..20);
+    assert_eq!(sub2.summary(),(110.0*scale,119.0*scale),"cutting disk block: sub block inside a page");
+    assert_eq!(sub2.get_sample(11),None,"cutting disk block: sub block sample out of range");
+
+    let bytes8 = DiskLayout{ width:1, ..layout };
+    assert!(DiskBlock::open(&file,bytes8).is_err(),"disk block: 8 bit samples get turned away");
+  }
+}
@@ -19,9 +19,9 @@ impl SilentBlock {
     Self::new(end-start)
   }
 
-  pub fn get_sample(&self,index:usize) -> Option<&f32> {
+  pub fn get_sample(&self,index:usize) -> Option<f32> {
     if index < self.len {
-      Some(&0.0)
+      Some(0.0)
     }
     else {
       None
@@ -46,11 +46,11 @@ mod tests {
     let sub1 = block.rng(10..50);
     assert_eq!(sub1.len(),40,"cutting silent block: sub block length");
     assert_eq!(sub1.summary(),(0.0,0.0),"cutting silent block: sub block summary");
-    assert_eq!(sub1.get_sample(4),Some(&0.0),"cutting silent block: sub block sample");
+    assert_eq!(sub1.get_sample(4),Some(0.0),"cutting silent block: sub block sample");
 
     let sub2 = sub1.rng(10..20);
     assert_eq!(sub2.len(),10,"cutting silent block: sub block length");
     assert_eq!(sub2.summary(),(0.0,0.0),"cutting silent block: sub block summary");
-    assert_eq!(sub2.get_sample(4),Some(&0.0),"cutting silent block: sub block sample");
+    assert_eq!(sub2.get_sample(4),Some(0.0),"cutting silent block: sub block sample");
   }
 }
@@ -40,7 +40,28 @@ impl Pyramid {
     Self::from_base(base)
   }
 
+  pub fn bytes(&self) -> usize {
+    self.mips.iter().map(|m|m.len() * std::mem::size_of::<(f32,f32)>()).sum()
+  }
+
+  //for stuff that's already been summarized, like the pages of a file on disk
+  pub fn pairs(vals:&[(f32,f32)]) -> Self {
+    let base : Vec<(f32,f32)> = vals.
+    chunks_exact(2).
+    map(|c|min_max_pair(c[0].0,c[1].0,c[0].1,c[1].1)).collect();
+
+    Self::from_base(base)
+  }
+
   pub fn peaks(&self,start:usize,end:usize,base:&[f32]) -> (f32,f32) {
+    self.peaks_with(start,end,|i|(base[i],base[i]))
+  }
+
+  pub fn pair_peaks(&self,start:usize,end:usize,base:&[(f32,f32)]) -> (f32,f32) {
+    self.peaks_with(start,end,|i|base[i])
+  }
+
+  fn peaks_with<F:Fn(usize)->(f32,f32)>(&self,start:usize,end:usize,leaf:F) -> (f32,f32) {
     let mut start = start;
     let mut min = f32::MAX;
     let mut max = f32::MIN;
@@ -49,7 +70,7 @@ impl Pyramid {
       let (asz,lod,idx) = power_chunk(start,end);
 
       let (cmin,cmax) = if asz == 1 {
-        (base[start],base[start])
+        leaf(start)
       }
       else {
         self.mips[lod-1][idx]
@@ -92,4 +113,14 @@ mod test {
     assert_eq!(pyr.peaks(257,600,&samples[..]),(257.0,599.0));
     assert_eq!(pyr.peaks(0,1000,&samples[..]),(0.0,999.0));
   }
+
+  #[test]
+  fn test_pairs() {
+    let pairs : Vec<(f32,f32)> = (0..100).map(|s|(-(s as f32),s as f32)).collect();
+    let pyr = Pyramid::pairs(&pairs[..]);
+
+    assert_eq!(pyr.pair_peaks(0,100,&pairs[..]),(-99.0,99.0),"the whole thing");
+    assert_eq!(pyr.pair_peaks(13,57,&pairs[..]),(-56.0,56.0),"an unaligned bit");
+    assert_eq!(pyr.pair_peaks(64,65,&pairs[..]),(-64.0,64.0),"a single pair");
+  }
 }
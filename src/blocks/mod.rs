@@ -3,5 +3,5 @@ mod mips;
 pub mod block;
 pub mod sequence;
 
-pub use block::Block;
+pub use block::{Block,DiskLayout};
 pub use sequence::BlockSequence;
@@ -39,21 +39,25 @@ impl BlockSequence {
     self.get_containing_block_index(idx).map(|i|&self.blocks[i])
   }
 
-  pub fn get_sample(&self,idx:&usize) -> Option<&f32> {
+  pub fn get_sample(&self,idx:&usize) -> Option<f32> {
     self.get_containing_block(idx).and_then(|(i,b)|{
       b.get_sample(idx-i)
     })
   }
 
+  pub fn storage(&self) -> impl Iterator<Item=(usize,usize)> + '_ {
+    self.blocks.iter().filter_map(|(_,b)|b.storage())
+  }
+
   pub fn summary(&self,st:usize,end:usize) -> (f32,f32) {
     if st > end {
-      let val = self.get_sample(&st).copied().unwrap();
+      let val = self.get_sample(&st).unwrap();
       return (val,val);
     }
 
     match end-st {
       0 if st < self.len() => {
-        let val = self.get_sample(&st).copied().unwrap();
+        let val = self.get_sample(&st).unwrap();
         (val,val)
       },
 
@@ -0,0 +1,179 @@
+use std::f64::consts::PI;
+
+//the shapes from the rbj audio eq cookbook, gains are in dB
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Filter {
+  Lowpass,
+  Highpass,
+  Bandpass,
+  Notch,
+  Allpass,
+  Peak(f64),
+  LowShelf(f64),
+  HighShelf(f64)
+}
+
+impl std::fmt::Display for Filter {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Filter::Lowpass => write!(f,"lowpass"),
+      Filter::Highpass => write!(f,"highpass"),
+      Filter::Bandpass => write!(f,"bandpass"),
+      Filter::Notch => write!(f,"notch"),
+      Filter::Allpass => write!(f,"allpass"),
+      Filter::Peak(g) => write!(f,"eq {}dB",g),
+      Filter::LowShelf(g) => write!(f,"low shelf {}dB",g),
+      Filter::HighShelf(g) => write!(f,"high shelf {}dB",g)
+    }
+  }
+}
+
+//a butterworth q, flat as it gets without ringing
+pub const DEFAULT_Q : f64 = std::f64::consts::FRAC_1_SQRT_2;
+
+//transposed direct form 2, the state is just the two delays so
+//one of these can get fed a block at a time and pick up where it left off
+#[derive(Debug,Clone,Copy)]
+pub struct Biquad {
+  b0:f64,
+  b1:f64,
+  b2:f64,
+  a1:f64,
+  a2:f64,
+  z1:f64,
+  z2:f64
+}
+
+impl Biquad {
+  pub fn new(kind:Filter,sample_rate:f64,freq:f64,q:f64) -> Self {
+    //keep the frequency off of 0 and nyquist or the coefficients blow up
+    let freq = freq.clamp(1.0,sample_rate*0.499);
+    let q = q.max(0.01);
+
+    let w0 = 2.0*PI*freq/sample_rate;
+    let (sin,cos) = w0.sin_cos();
+    let alpha = sin/(2.0*q);
+    let amp = |g:f64|10f64.powf(g/40.0);
+
+    let (b0,b1,b2,a0,a1,a2) = match kind {
+      Filter::Lowpass => ((1.0 - cos)/2.0,1.0 - cos,(1.0 - cos)/2.0,1.0 + alpha,-2.0*cos,1.0 - alpha),
+      Filter::Highpass => ((1.0 + cos)/2.0,-(1.0 + cos),(1.0 + cos)/2.0,1.0 + alpha,-2.0*cos,1.0 - alpha),
+      Filter::Bandpass => (alpha,0.0,-alpha,1.0 + alpha,-2.0*cos,1.0 - alpha),
+      Filter::Notch => (1.0,-2.0*cos,1.0,1.0 + alpha,-2.0*cos,1.0 - alpha),
+      Filter::Allpass => (1.0 - alpha,-2.0*cos,1.0 + alpha,1.0 + alpha,-2.0*cos,1.0 - alpha),
+
+      Filter::Peak(g) => {
+        let a = amp(g);
+        (1.0 + alpha*a,-2.0*cos,1.0 - alpha*a,1.0 + alpha/a,-2.0*cos,1.0 - alpha/a)
+      },
+
+      Filter::LowShelf(g) => {
+        let a = amp(g);
+        let sq = 2.0*a.sqrt()*alpha;
+        (
+          a*((a + 1.0) - (a - 1.0)*cos + sq),
+          2.0*a*((a - 1.0) - (a + 1.0)*cos),
+          a*((a + 1.0) - (a - 1.0)*cos - sq),
+          (a + 1.0) + (a - 1.0)*cos + sq,
+          -2.0*((a - 1.0) + (a + 1.0)*cos),
+          (a + 1.0) + (a - 1.0)*cos - sq
+        )
+      },
+
+      Filter::HighShelf(g) => {
+        let a = amp(g);
+        let sq = 2.0*a.sqrt()*alpha;
+        (
+          a*((a + 1.0) + (a - 1.0)*cos + sq),
+          -2.0*a*((a - 1.0) + (a + 1.0)*cos),
+          a*((a + 1.0) + (a - 1.0)*cos - sq),
+          (a + 1.0) - (a - 1.0)*cos + sq,
+          2.0*((a - 1.0) - (a + 1.0)*cos),
+          (a + 1.0) - (a - 1.0)*cos - sq
+        )
+      }
+    };
+
+    Self::from_coefficients([b0,b1,b2],[a0,a1,a2])
+  }
+
+  //for the filters that don't come out of the cookbook
+  pub fn from_coefficients(b:[f64;3],a:[f64;3]) -> Self {
+    Self {
+      b0:b[0]/a[0],
+      b1:b[1]/a[0],
+      b2:b[2]/a[0],
+      a1:a[1]/a[0],
+      a2:a[2]/a[0],
+      z1:0.0,
+      z2:0.0
+    }
+  }
+
+  pub fn process(&mut self,x:f32) -> f32 {
+    let x = x as f64;
+    let y = self.b0*x + self.z1;
+    self.z1 = self.b1*x - self.a1*y + self.z2;
+    self.z2 = self.b2*x - self.a2*y;
+    y as f32
+  }
+
+  //the gain at a frequency, for checking the shapes
+  #[cfg(test)]
+  pub fn response(&self,sample_rate:f64,freq:f64) -> f64 {
+    let w = 2.0*PI*freq/sample_rate;
+    let (s1,c1) = w.sin_cos();
+    let (s2,c2) = (2.0*w).sin_cos();
+
+    let num = (self.b0 + self.b1*c1 + self.b2*c2,-(self.b1*s1 + self.b2*s2));
+    let den = (1.0 + self.a1*c1 + self.a2*c2,-(self.a1*s1 + self.a2*s2));
+    ((num.0*num.0 + num.1*num.1)/(den.0*den.0 + den.1*den.1)).sqrt()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn db(g:f64) -> f64 {
+    20.0*g.log10()
+  }
+
+  #[test]
+  fn test_shapes() {
+    let sr = 48000.0;
+    let lp = Biquad::new(Filter::Lowpass,sr,1000.0,DEFAULT_Q);
+    assert!((db(lp.response(sr,1000.0)) + 3.0).abs() < 0.1,"lowpass is 3dB down at the corner");
+    assert!(db(lp.response(sr,10000.0)) < -35.0,"lowpass cuts the highs");
+
+    let hp = Biquad::new(Filter::Highpass,sr,100.0,DEFAULT_Q);
+    assert!(db(hp.response(sr,10.0)) < -35.0,"highpass cuts rumble");
+    assert!(db(hp.response(sr,5000.0)).abs() < 0.1,"highpass leaves the highs");
+
+    let eq = Biquad::new(Filter::Peak(6.0),sr,2000.0,1.0);
+    assert!((db(eq.response(sr,2000.0)) - 6.0).abs() < 0.1,"peak hits the gain at the center");
+    assert!(db(eq.response(sr,50.0)).abs() < 0.1,"peak leaves everything else alone");
+
+    let shelf = Biquad::new(Filter::LowShelf(-6.0),sr,200.0,DEFAULT_Q);
+    assert!((db(shelf.response(sr,10.0)) + 6.0).abs() < 0.1,"low shelf hits the gain down low");
+    assert!(db(shelf.response(sr,10000.0)).abs() < 0.1,"low shelf leaves the highs");
+
+    let notch = Biquad::new(Filter::Notch,sr,60.0,10.0);
+    assert!(db(notch.response(sr,60.0)) < -60.0,"notch gets rid of hum");
+  }
+
+  #[test]
+  fn test_dc() {
+    let mut lp = Biquad::new(Filter::Lowpass,44100.0,500.0,DEFAULT_Q);
+    let mut hp = Biquad::new(Filter::Highpass,44100.0,500.0,DEFAULT_Q);
+    let (mut l,mut h) = (0.0,0.0);
+
+    for _ in 0..10000 {
+      l = lp.process(1.0);
+      h = hp.process(1.0);
+    }
+
+    assert!((l - 1.0).abs() < 1e-4,"lowpass lets dc through");
+    assert!(h.abs() < 1e-4,"highpass blocks dc");
+  }
+}
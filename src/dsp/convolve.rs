@@ -0,0 +1,105 @@
+use super::fft::{Fft,Complex};
+
+//uniformly partitioned overlap-save. the impulse response gets cut into
+//block sized pieces that each get their own spectrum, and the spectra of the
+//last few blocks of input get kept around to multiply against them. long
+//responses cost a multiply per partition instead of one giant fft
+pub struct Convolver {
+  block:usize,
+  fft:Fft,
+  parts:Vec<Vec<Complex>>,
+  history:Vec<Vec<Complex>>,
+  newest:usize,
+  input:Vec<f32>
+}
+
+const MIN_BLOCK : usize = 64;
+const MAX_BLOCK : usize = 4096;
+
+impl Convolver {
+  pub fn new(ir:&[f32]) -> Self {
+    let block = ir.len().next_power_of_two().clamp(MIN_BLOCK,MAX_BLOCK);
+    let fft = Fft::new(block*2);
+
+    let parts : Vec<Vec<Complex>> = ir.chunks(block).map(|c|{
+      let mut spec = vec![Complex::default();block*2];
+      spec.iter_mut().zip(c.iter()).for_each(|(s,v)|*s = Complex::new(*v as f64,0.0));
+      fft.forward(&mut spec);
+      spec
+    }).collect();
+
+    let history = vec![vec![Complex::default();block*2];parts.len().max(1)];
+    Self{ block, fft, parts, history, newest:0, input:vec![0.0;block*2] }
+  }
+
+  pub fn block_len(&self) -> usize {
+    self.block
+  }
+
+  //takes exactly one block of input and gives back one block of output
+  pub fn process(&mut self,block:&[f32]) -> Vec<f32> {
+    let b = self.block;
+
+    //the last block and this one, the first half of the result wraps around and gets tossed
+    self.input.copy_within(b.., 0);
+    self.input[b..].iter_mut().zip(block.iter().chain(std::iter::repeat(&0.0))).for_each(|(i,s)|*i = *s);
+
+    let count = self.history.len();
+    self.newest = (self.newest + 1) % count;
+    let spec = &mut self.history[self.newest];
+    spec.iter_mut().zip(self.input.iter()).for_each(|(c,s)|*c = Complex::new(*s as f64,0.0));
+    self.fft.forward(spec);
+
+    let mut acc = vec![Complex::default();b*2];
+    for (p,part) in self.parts.iter().enumerate() {
+      let past = &self.history[(self.newest + count - p) % count];
+      acc.iter_mut().zip(past.iter().zip(part.iter())).for_each(|(a,(x,h))|*a += *x * *h);
+    }
+
+    self.fft.inverse(&mut acc);
+    acc[b..].iter().map(|c|c.re as f32).collect()
+  }
+}
+
+//the first len samples of the signal through the impulse response,
+//anything past the end of the signal is silence
+pub fn convolve(signal:&[f32],ir:&[f32],len:usize) -> Vec<f32> {
+  let mut conv = Convolver::new(ir);
+  let b = conv.block_len();
+  let mut out = Vec::with_capacity(len + b);
+
+  for i in (0..len).step_by(b) {
+    let end = (i + b).min(signal.len());
+    let input = if i < end { &signal[i..end] } else { &[] };
+    out.extend(conv.process(input));
+  }
+
+  out.truncate(len);
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_matches_direct_convolution() {
+    let signal : Vec<f32> = (0..10000).map(|i|((i*7919) % 200) as f32/100.0 - 1.0).collect();
+
+    //short enough for one partition and long enough for several
+    for ir_len in [3,100,9000] {
+      let ir : Vec<f32> = (0..ir_len).map(|i|((i*104729) % 97) as f32/97.0 - 0.5).collect();
+      let len = signal.len() + ir.len() - 1;
+      let got = convolve(&signal,&ir,len);
+
+      let mut worst = 0.0f32;
+      for n in (0..len).step_by(37) {
+        let direct : f32 = (0..ir.len()).filter(|k|*k <= n && n - k < signal.len()).map(|k|ir[k]*signal[n-k]).sum();
+        worst = worst.max((direct - got[n]).abs());
+      }
+
+      assert_eq!(got.len(),len,"the output should be as long as asked for");
+      assert!(worst < 1e-3,"{} samples of ir is off from the direct sum by {}",ir_len,worst);
+    }
+  }
+}
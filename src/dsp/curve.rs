@@ -0,0 +1,67 @@
+use std::f32::consts::{PI,FRAC_PI_2};
+
+//the shape of a fade, gain goes from 0 to 1 as t does.
+//fading out is the same curve run backwards
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum Curve {
+  Linear,
+  //straight lines in dB, slow to start and quick to finish
+  Exponential,
+  //the other way around, quick to start and slow to finish
+  Logarithmic,
+  //keeps the power constant across a crossfade of uncorrelated material
+  #[default]
+  EqualPower,
+  //eases in and out at both ends
+  SShape
+}
+
+//how far down in dB the exponential curves start from
+const RANGE_DB : f32 = 60.0;
+
+fn db_ramp(t:f32) -> f32 {
+  let floor = 10f32.powf(-RANGE_DB/20.0);
+  let g = 10f32.powf((t - 1.0) * RANGE_DB/20.0);
+  (g - floor)/(1.0 - floor)
+}
+
+impl Curve {
+  pub fn gain(&self,t:f32) -> f32 {
+    let t = t.clamp(0.0,1.0);
+
+    match self {
+      Curve::Linear => t,
+      Curve::Exponential => db_ramp(t),
+      Curve::Logarithmic => 1.0 - db_ramp(1.0 - t),
+      Curve::EqualPower => (t * FRAC_PI_2).sin(),
+      Curve::SShape => (1.0 - (t * PI).cos()) * 0.5
+    }
+  }
+}
+
+impl std::str::FromStr for Curve {
+  type Err = anyhow::Error;
+
+  fn from_str(s:&str) -> anyhow::Result<Self> {
+    match s {
+      "linear" | "lin" => Ok(Curve::Linear),
+      "exponential" | "exp" => Ok(Curve::Exponential),
+      "logarithmic" | "log" => Ok(Curve::Logarithmic),
+      "equal_power" | "equal" | "power" | "sine" => Ok(Curve::EqualPower),
+      "s_curve" | "s" => Ok(Curve::SShape),
+      _ => Err(anyhow::anyhow!("unknown curve {}, try linear, exp, log, equal_power or s_curve",s))
+    }
+  }
+}
+
+impl std::fmt::Display for Curve {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Curve::Linear => write!(f,"linear"),
+      Curve::Exponential => write!(f,"exp"),
+      Curve::Logarithmic => write!(f,"log"),
+      Curve::EqualPower => write!(f,"equal_power"),
+      Curve::SShape => write!(f,"s_curve")
+    }
+  }
+}
@@ -0,0 +1,124 @@
+use super::{Biquad,Filter,DEFAULT_Q,TAIL_SILENCE,MAX_TAIL_SECS};
+
+//anything at or past 1 would just keep getting louder
+const MAX_FEEDBACK : f32 = 0.99;
+
+//time is in samples. the filters sit in the feedback loop so each repeat
+//gets darker or thinner than the last. ping pong sends the input down the
+//first channel and every repeat hops over to the next one
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Delay {
+  pub time:f64,
+  pub feedback:f32,
+  pub lowpass:Option<f64>,
+  pub highpass:Option<f64>,
+  pub ping_pong:bool,
+  pub mix:f32
+}
+
+impl Delay {
+  pub fn new(time:f64) -> Self {
+    Self{ time, feedback:0.4, lowpass:None, highpass:None, ping_pong:false, mix:0.5 }
+  }
+
+  fn feedback(&self) -> f32 {
+    self.feedback.clamp(-MAX_FEEDBACK,MAX_FEEDBACK)
+  }
+
+  //how long until the repeats are too quiet to matter
+  pub fn tail(&self,sr:f64) -> usize {
+    let time = self.time.max(1.0);
+    let fb = self.feedback().abs();
+
+    let repeats = if fb > 0.0 { (TAIL_SILENCE.ln()/fb.ln()).ceil() as f64 } else { 0.0 };
+    (time*(repeats + 1.0)).min(MAX_TAIL_SECS*sr) as usize
+  }
+
+  pub fn processor(&self,sr:f64,channels:usize) -> Echo {
+    let len = self.time.round().max(1.0) as usize;
+
+    let filters = (0..channels).map(|_|{
+      let lp = self.lowpass.map(|f|Biquad::new(Filter::Lowpass,sr,f,DEFAULT_Q));
+      let hp = self.highpass.map(|f|Biquad::new(Filter::Highpass,sr,f,DEFAULT_Q));
+      lp.into_iter().chain(hp).collect()
+    }).collect();
+
+    let delay = Delay{ feedback:self.feedback(), ..*self };
+    Echo{ delay, lines:vec![vec![0.0;len];channels], at:0, filters }
+  }
+}
+
+pub struct Echo {
+  delay:Delay,
+  lines:Vec<Vec<f32>>,
+  at:usize,
+  filters:Vec<Vec<Biquad>>
+}
+
+impl Echo {
+  //one sample per channel, processed in place
+  pub fn process(&mut self,frame:&mut [f32]) {
+    let d = &self.delay;
+    let n = frame.len().min(self.lines.len());
+
+    let outs : Vec<f32> = (0..n).map(|c|{
+      self.filters[c].iter_mut().fold(self.lines[c][self.at],|s,f|f.process(s))
+    }).collect();
+
+    let mono = frame.iter().sum::<f32>()/n.max(1) as f32;
+
+    for c in 0..n {
+      let (input,back) = if d.ping_pong {
+        (if c == 0 { mono } else { 0.0 },outs[(c + n - 1) % n])
+      }
+      else {
+        (frame[c],outs[c])
+      };
+
+      self.lines[c][self.at] = input + back*d.feedback;
+      frame[c] = frame[c]*(1.0 - d.mix) + outs[c]*d.mix;
+    }
+
+    self.at = (self.at + 1) % self.lines.first().map(|l|l.len()).unwrap_or(1);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn run(d:&Delay,channels:usize,input:&[f32],len:usize) -> Vec<Vec<f32>> {
+    let mut echo = d.processor(44100.0,channels);
+    let mut outs = vec![vec![];channels];
+
+    for i in 0..len {
+      let mut frame = vec![input.get(i).copied().unwrap_or(0.0);channels];
+      echo.process(&mut frame);
+      outs.iter_mut().zip(frame).for_each(|(o,s)|o.push(s));
+    }
+
+    outs
+  }
+
+  #[test]
+  fn test_repeats() {
+    let d = Delay{ feedback:0.5, mix:1.0, ..Delay::new(100.0) };
+    let out = run(&d,1,&[1.0],400);
+
+    assert_eq!(out[0][0],0.0,"all wet means no dry");
+    assert_eq!(out[0][100],1.0,"the first repeat comes at the delay time");
+    assert_eq!(out[0][200],0.5,"each repeat gets the feedback");
+    assert_eq!(out[0][300],0.25,"and so on");
+    assert_eq!(d.tail(44100.0),1100,"the tail runs until the repeats are 60dB down");
+
+    let runaway = Delay{ feedback:1.5, ..d };
+    let out = run(&runaway,1,&[1.0],runaway.tail(44100.0));
+    assert!(out[0].iter().all(|s|s.abs() <= 1.0),"feedback past 1 shouldn't blow up");
+
+    let pp = Delay{ ping_pong:true, ..d };
+    let out = run(&pp,2,&[1.0],400);
+    assert_eq!((out[0][100],out[1][100]),(1.0,0.0),"ping pong starts on the left");
+    assert_eq!((out[0][200],out[1][200]),(0.0,0.5),"and bounces right");
+    assert_eq!((out[0][300],out[1][300]),(0.25,0.0),"and back");
+  }
+}
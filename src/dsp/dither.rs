@@ -0,0 +1,133 @@
+//dither gets added when floats get squashed down to fewer bits,
+//without it quiet stuff turns into crunchy quantization distortion
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum Dither {
+  Off,
+  #[default]
+  Tpdf,
+  Shaped
+}
+
+impl std::str::FromStr for Dither {
+  type Err = anyhow::Error;
+
+  fn from_str(s:&str) -> anyhow::Result<Self> {
+    match s {
+      "off" | "none" => Ok(Dither::Off),
+      "tpdf" => Ok(Dither::Tpdf),
+      "shaped" => Ok(Dither::Shaped),
+      _ => Err(anyhow::anyhow!("unknown dither mode {}, try off, tpdf or shaped",s))
+    }
+  }
+}
+
+impl std::fmt::Display for Dither {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Dither::Off => write!(f,"off"),
+      Dither::Tpdf => write!(f,"tpdf"),
+      Dither::Shaped => write!(f,"shaped")
+    }
+  }
+}
+
+//these are the wannamaker 3 tap "F-weighted" error feedback coefficients,
+//they push the noise up towards nyquist where it's harder to hear
+const SHAPE : [f32;3] = [1.623,-0.982,0.109];
+
+//turns floats into integers with a given number of bits, one of these
+//should be used per file since the noise shaping keeps state per channel
+pub struct Quantizer {
+  mode:Dither,
+  scale:f32,
+  min:f32,
+  max:f32,
+  seed:u32,
+  errs:Vec<[f32;3]>
+}
+
+impl Quantizer {
+  pub fn new(mode:Dither,bits:u16,channels:usize) -> Self {
+    let scale = (1u64 << (bits - 1)) as f32;
+
+    Self {
+      mode,
+      scale,
+      min:-scale,
+      max:scale - 1.0,
+      seed:0x9E3779B9,
+      errs:vec![[0.0;3];channels]
+    }
+  }
+
+  //xorshift, we don't need anything fancy for noise
+  fn rand(&mut self) -> f32 {
+    self.seed ^= self.seed << 13;
+    self.seed ^= self.seed >> 17;
+    self.seed ^= self.seed << 5;
+    self.seed as f32 / u32::MAX as f32
+  }
+
+  //triangular noise +/- 1 LSB
+  fn tpdf(&mut self) -> f32 {
+    self.rand() - self.rand()
+  }
+
+  pub fn quantize(&mut self,smp:f32,chan:usize) -> i32 {
+    let x = smp * self.scale;
+
+    match self.mode {
+      Dither::Off => x.round().clamp(self.min,self.max) as i32,
+
+      Dither::Tpdf => {
+        let d = self.tpdf();
+        (x + d).round().clamp(self.min,self.max) as i32
+      },
+
+      Dither::Shaped => {
+        let d = self.tpdf();
+        let e = self.errs[chan];
+        let v = x - (SHAPE[0]*e[0] + SHAPE[1]*e[1] + SHAPE[2]*e[2]);
+        let q = (v + d).round().clamp(self.min,self.max);
+
+        //clipping makes for huge errors, which would blow up the feedback
+        let err = (q - v).clamp(-1.0,1.0);
+        self.errs[chan] = [err,e[0],e[1]];
+        q as i32
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_clamping() {
+    let mut q = Quantizer::new(Dither::Off,16,1);
+    assert_eq!(q.quantize(1.0,0),32767,"full scale should clamp to the max");
+    assert_eq!(q.quantize(-1.0,0),-32768,"negative full scale should hit the min");
+    assert_eq!(q.quantize(0.5,0),16384,"half scale should be half scale");
+
+    let mut q = Quantizer::new(Dither::Off,24,1);
+    assert_eq!(q.quantize(1.0,0),8388607,"24 bit full scale should clamp");
+  }
+
+  #[test]
+  fn test_dither_stays_close() {
+    for mode in [Dither::Tpdf,Dither::Shaped] {
+      let mut q = Quantizer::new(mode,16,2);
+      let mut sum = 0.0f64;
+
+      for i in 0..10000 {
+        let out = q.quantize(0.25,i%2);
+        assert!((out - 8192).abs() <= 6,"dithered output should stay within a few LSBs");
+        sum += out as f64;
+      }
+
+      let mean = sum/10000.0;
+      assert!((mean - 8192.0).abs() < 0.1,"the dither should average out to the signal");
+    }
+  }
+}
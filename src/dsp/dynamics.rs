@@ -0,0 +1,210 @@
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Mode {
+  Compressor,
+  Limiter,
+  Expander,
+  Gate
+}
+
+impl std::fmt::Display for Mode {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Mode::Compressor => write!(f,"compress"),
+      Mode::Limiter => write!(f,"limit"),
+      Mode::Expander => write!(f,"expand"),
+      Mode::Gate => write!(f,"gate")
+    }
+  }
+}
+
+//levels are all dB, times are all ms. compressors and limiters work above
+//the threshold, expanders and gates below it. range is as far down as
+//an expander or gate will go
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Dynamics {
+  pub mode:Mode,
+  pub threshold:f32,
+  pub ratio:f32,
+  pub knee:f32,
+  pub attack:f32,
+  pub release:f32,
+  pub lookahead:f32,
+  pub range:f32,
+  pub makeup:f32
+}
+
+impl Dynamics {
+  pub fn new(mode:Mode) -> Self {
+    let base = Self{
+      mode,
+      threshold:-20.0,
+      ratio:4.0,
+      knee:6.0,
+      attack:10.0,
+      release:100.0,
+      lookahead:0.0,
+      range:-80.0,
+      makeup:0.0
+    };
+
+    match mode {
+      Mode::Compressor => base,
+      Mode::Limiter => Self{ threshold:-1.0, knee:0.0, attack:1.0, release:50.0, lookahead:2.0, ..base },
+      Mode::Expander => Self{ threshold:-40.0, ratio:2.0, ..base },
+      Mode::Gate => Self{ threshold:-50.0, knee:0.0, attack:1.0, release:200.0, ..base }
+    }
+  }
+
+  //the static curve, how many dB to turn a level down by
+  pub fn reduction(&self,level:f32) -> f32 {
+    let w = self.knee.max(0.0);
+    let d = level - self.threshold;
+
+    match self.mode {
+      Mode::Compressor | Mode::Limiter => {
+        let slope = if self.mode == Mode::Limiter { -1.0 } else { 1.0/self.ratio.max(1.0) - 1.0 };
+
+        if w > 0.0 && d.abs() <= w/2.0 {
+          slope * (d + w/2.0).powi(2)/(2.0*w)
+        }
+        else {
+          slope * d.max(0.0)
+        }
+      },
+
+      Mode::Expander | Mode::Gate => {
+        //a gate is just a really steep expander
+        let slope = if self.mode == Mode::Gate { 100.0 } else { self.ratio.max(1.0) - 1.0 };
+
+        let r = if w > 0.0 && d.abs() <= w/2.0 {
+          -slope * (d - w/2.0).powi(2)/(2.0*w)
+        }
+        else {
+          slope * d.min(0.0)
+        };
+
+        r.max(self.range.min(0.0))
+      }
+    }
+  }
+
+  pub fn lookahead_samples(&self,sample_rate:f64) -> usize {
+    (self.lookahead.max(0.0) as f64 * sample_rate / 1000.0).round() as usize
+  }
+
+  //takes the detector levels one frame at a time and hands back linear gains
+  //for the same frames. with lookahead each frame's target is the most any of
+  //the next lookahead frames want, so the gain is already down when a peak
+  //gets there. the levels should run lookahead frames past the audio
+  pub fn gains<I:Iterator<Item=f32>>(&self,sample_rate:f64,levels:I) -> Vec<f32> {
+    let coef = |ms:f32|{
+      if ms <= 0.0 {
+        0.0
+      }
+      else {
+        (-1000.0/(ms as f64 * sample_rate)).exp() as f32
+      }
+    };
+
+    let (att,rel) = (coef(self.attack),coef(self.release));
+    let turning_down = matches!(self.mode,Mode::Compressor | Mode::Limiter);
+
+    let targets : Vec<f32> = levels.map(|lvl|self.reduction(20.0*lvl.abs().max(1e-9).log10())).collect();
+    let ahead = window_extreme(&targets,self.lookahead_samples(sample_rate),turning_down);
+    let mut g = 0.0f32;
+
+    targets.iter().zip(ahead).map(|(now,target)|{
+      //attack is clamping down for compressors and opening up for gates
+      let attacking = if turning_down { target < g } else { target > g };
+      let c = if attacking { att } else { rel };
+      g = target + c*(g - target);
+
+      //a slow attack can't be allowed to let anything past a limiter
+      if self.mode == Mode::Limiter {
+        g = g.min(*now);
+      }
+
+      10f32.powf((g + self.makeup)/20.0)
+    }).collect()
+  }
+}
+
+//the lowest (or highest) of each value and the len after it, keeping a queue
+//of the ones that could still be the answer so it's one pass
+fn window_extreme(vals:&[f32],len:usize,lowest:bool) -> Vec<f32> {
+  let better = |a:f32,b:f32|if lowest { a <= b } else { a >= b };
+  let mut q = std::collections::VecDeque::new();
+  let mut out = vec![0.0;vals.len()];
+
+  for i in (0..vals.len()).rev() {
+    while q.back().is_some_and(|j:&usize|better(vals[i],vals[*j])) {
+      q.pop_back();
+    }
+
+    q.push_back(i);
+
+    while q.front().is_some_and(|j|*j > i + len) {
+      q.pop_front();
+    }
+
+    out[i] = vals[q[0]];
+  }
+
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_static_curves() {
+    let comp = Dynamics{ knee:0.0, ..Dynamics::new(Mode::Compressor) };
+    assert_eq!(comp.reduction(-30.0),0.0,"nothing happens under the threshold");
+    assert_eq!(comp.reduction(-10.0),-7.5,"4:1 over by 10dB comes down 7.5");
+
+    let soft = Dynamics::new(Mode::Compressor);
+    assert!(soft.reduction(-20.0) < 0.0 && soft.reduction(-20.0) > -1.0,"the knee starts early and gently");
+    assert!((soft.reduction(-10.0) + 7.5).abs() < 1e-4,"past the knee it's the same as hard");
+
+    let lim = Dynamics::new(Mode::Limiter);
+    assert_eq!(lim.reduction(3.0),-4.0,"limiters stop everything at the threshold");
+
+    let exp = Dynamics{ knee:0.0, ..Dynamics::new(Mode::Expander) };
+    assert_eq!(exp.reduction(-50.0),-10.0,"1:2 under by 10dB goes down another 10");
+    assert_eq!(exp.reduction(-30.0),0.0,"expanders leave the loud stuff");
+
+    let gate = Dynamics::new(Mode::Gate);
+    assert_eq!(gate.reduction(-70.0),-80.0,"gates go down to the range");
+  }
+
+  #[test]
+  fn test_envelope() {
+    let lim = Dynamics{ attack:0.0, lookahead:0.0, ..Dynamics::new(Mode::Limiter) };
+    let ceiling = 10f32.powf(-1.0/20.0);
+    let sig : Vec<f32> = (0..4800).map(|i|(i as f32 * 0.05).sin()).collect();
+    let gains = lim.gains(48000.0,sig.iter().copied());
+    let peak = sig.iter().zip(gains.iter()).fold(0.0f32,|m,(s,g)|m.max((s*g).abs()));
+    assert!(peak <= ceiling + 1e-4,"an instant limiter never goes over, got {}",peak);
+
+    let comp = Dynamics::new(Mode::Compressor);
+    let gains = comp.gains(48000.0,std::iter::repeat_n(1.0,48000));
+    assert!(gains[10] > gains[4000],"the attack takes a bit to clamp down");
+    assert!((gains[47999] - 10f32.powf(-15.0/20.0)).abs() < 1e-3,"it settles on the static curve");
+  }
+
+  #[test]
+  fn test_lookahead_catches_transients() {
+    let lim = Dynamics::new(Mode::Limiter);
+    let ceiling = 10f32.powf(lim.threshold/20.0);
+
+    //one loud sample in the middle of quiet
+    let sig : Vec<f32> = (0..4800).map(|i|if i == 2400 { 1.0 } else { 0.01 }).collect();
+    let gains = lim.gains(48000.0,sig.iter().copied());
+    let peak = sig.iter().zip(gains.iter()).fold(0.0f32,|m,(s,g)|m.max((s*g).abs()));
+    assert!(peak <= ceiling + 1e-6,"a lone peak shouldn't get past the limiter, got {}",peak);
+    assert!(gains[2400 - lim.lookahead_samples(48000.0)] < 1.0,"the gain should start down early");
+
+    assert_eq!(window_extreme(&[3.0,1.0,2.0,5.0,4.0],1,true),vec![1.0,1.0,2.0,4.0,4.0],"the lowest of each pair");
+  }
+}
@@ -0,0 +1,128 @@
+use std::f64::consts::PI;
+use std::ops::{Add,Sub,Mul,AddAssign};
+
+#[derive(Debug,Clone,Copy,Default,PartialEq)]
+pub struct Complex {
+  pub re:f64,
+  pub im:f64
+}
+
+impl Complex {
+  pub fn new(re:f64,im:f64) -> Self {
+    Self{ re, im }
+  }
+
+  fn conj(self) -> Self {
+    Self{ re:self.re, im:-self.im }
+  }
+}
+
+impl Add for Complex {
+  type Output = Self;
+  fn add(self,o:Self) -> Self { Self{ re:self.re + o.re, im:self.im + o.im } }
+}
+
+impl Sub for Complex {
+  type Output = Self;
+  fn sub(self,o:Self) -> Self { Self{ re:self.re - o.re, im:self.im - o.im } }
+}
+
+impl Mul for Complex {
+  type Output = Self;
+  fn mul(self,o:Self) -> Self {
+    Self{ re:self.re*o.re - self.im*o.im, im:self.re*o.im + self.im*o.re }
+  }
+}
+
+impl AddAssign for Complex {
+  fn add_assign(&mut self,o:Self) { *self = *self + o }
+}
+
+//plain radix 2, sizes have to be a power of two.
+//the twiddles and the bit reversal get worked out once up front
+pub struct Fft {
+  n:usize,
+  twiddles:Vec<Complex>,
+  rev:Vec<usize>
+}
+
+impl Fft {
+  pub fn new(n:usize) -> Self {
+    assert!(n.is_power_of_two(),"fft sizes have to be a power of two");
+    let bits = n.trailing_zeros();
+
+    let twiddles = (0..n/2).map(|k|{
+      let ph = -2.0*PI*k as f64/n as f64;
+      Complex::new(ph.cos(),ph.sin())
+    }).collect();
+
+    let rev = (0..n).map(|i|if bits == 0 { 0 } else { i.reverse_bits() >> (usize::BITS - bits) }).collect();
+    Self{ n, twiddles, rev }
+  }
+
+  pub fn forward(&self,buf:&mut [Complex]) {
+    self.transform(buf,false)
+  }
+
+  //scaled so forward then inverse gets back where it started
+  pub fn inverse(&self,buf:&mut [Complex]) {
+    self.transform(buf,true);
+    let scale = 1.0/self.n as f64;
+    buf.iter_mut().for_each(|c|*c = Complex::new(c.re*scale,c.im*scale));
+  }
+
+  fn transform(&self,buf:&mut [Complex],inverse:bool) {
+    let n = self.n;
+
+    for i in 0..n {
+      let j = self.rev[i];
+      if j > i {
+        buf.swap(i,j);
+      }
+    }
+
+    let mut len = 2;
+    while len <= n {
+      let half = len/2;
+      let stride = n/len;
+
+      for start in (0..n).step_by(len) {
+        for j in 0..half {
+          let w = self.twiddles[j*stride];
+          let w = if inverse { w.conj() } else { w };
+          let u = buf[start + j];
+          let v = buf[start + j + half]*w;
+          buf[start + j] = u + v;
+          buf[start + j + half] = u - v;
+        }
+      }
+
+      len *= 2;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_fft() {
+    let fft = Fft::new(64);
+
+    //a cosine sitting right on bin 5 should land there and nowhere else
+    let mut buf : Vec<Complex> = (0..64).map(|i|Complex::new((2.0*PI*5.0*i as f64/64.0).cos(),0.0)).collect();
+    let orig = buf.clone();
+    fft.forward(&mut buf);
+
+    for (k,c) in buf.iter().enumerate() {
+      let mag = (c.re*c.re + c.im*c.im).sqrt();
+      let expect = if k == 5 || k == 59 { 32.0 } else { 0.0 };
+      assert!((mag - expect).abs() < 1e-9,"bin {} should be {} not {}",k,expect,mag);
+    }
+
+    fft.inverse(&mut buf);
+    let err = buf.iter().zip(orig.iter()).fold(0.0f64,|m,(a,b)|m.max((a.re - b.re).abs()).max(a.im.abs()));
+    assert!(err < 1e-12,"the inverse should get the signal back");
+  }
+}
@@ -0,0 +1,243 @@
+use super::{Biquad,functions};
+
+//everything the meter found, loudness in LUFS, range in LU and true peak in dBTP.
+//silence comes out as -inf
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Loudness {
+  pub integrated:f64,
+  pub range:f64,
+  pub true_peak:f64,
+  pub momentary_max:f64,
+  pub short_term_max:f64
+}
+
+//bs.1770 measures in 400ms blocks that overlap by 75%, so everything
+//gets summed up 100ms at a time and the blocks get built out of those
+const SUB_BLOCK_MS : f64 = 100.0;
+const MOMENTARY : usize = 4;
+const SHORT_TERM : usize = 30;
+const ABSOLUTE_GATE : f64 = -70.0;
+
+fn lufs(mean_square:f64) -> f64 {
+  -0.691 + 10.0*mean_square.log10()
+}
+
+//surrounds count a bit more and the lfe doesn't count at all, for 5.1
+fn weight(chan:usize,channels:usize) -> f64 {
+  if channels == 6 {
+    [1.0,1.0,1.0,0.0,1.41,1.41][chan]
+  }
+  else {
+    1.0
+  }
+}
+
+//the k weighting is a high shelf for the head and a highpass for the rumble.
+//they're built from the analog prototypes so they land on the spec's
+//coefficients at 48k and still work at any other sample rate
+fn k_weighting(sample_rate:f64) -> [Biquad;2] {
+  use std::f64::consts::PI;
+
+  let (f0,gain,q) = (1_681.974_450_955_533,3.999_843_853_973_347,0.707_175_236_955_419_6);
+  let k = (PI * f0/sample_rate).tan();
+  let vh = 10f64.powf(gain/20.0);
+  let vb = vh.powf(0.499_666_774_154_541_6);
+  let shelf = Biquad::from_coefficients(
+    [vh + vb*k/q + k*k,2.0*(k*k - vh),vh - vb*k/q + k*k],
+    [1.0 + k/q + k*k,2.0*(k*k - 1.0),1.0 - k/q + k*k]
+  );
+
+  let (f0,q) = (38.135_470_876_139_82,0.500_327_037_325_395_3);
+  let k = (PI * f0/sample_rate).tan();
+  let a0 = 1.0 + k/q + k*k;
+  let highpass = Biquad::from_coefficients(
+    [1.0,-2.0,1.0],
+    [1.0,2.0*(k*k - 1.0)/a0,(1.0 - k/q + k*k)/a0]
+  );
+
+  [shelf,highpass]
+}
+
+//4x oversampling finds the peaks that land between samples, well above
+//the 192k the spec says is enough it's not worth doing
+const TAPS : usize = 12;
+
+struct TruePeak {
+  phases:Vec<Vec<f32>>,
+  history:Vec<f32>,
+  head:usize,
+  peak:f32
+}
+
+impl TruePeak {
+  fn new(sample_rate:f64) -> Self {
+    let factor = if sample_rate >= 192000.0 { 1 } else { 4 };
+
+    //the phase p filter is a windowed sinc shifted by p/factor of a sample
+    let phases = (0..factor).map(|p|{
+      let frac = p as f32/factor as f32;
+      (0..2*TAPS).map(|k|{
+        let ph = (TAPS as f32 - 1.0 - k as f32) + frac;
+        functions::sinc(ph) * functions::blackman_window(ph,TAPS as f32)
+      }).collect()
+    }).collect();
+
+    Self{ phases, history:vec![0.0;2*TAPS], head:0, peak:0.0 }
+  }
+
+  fn push(&mut self,x:f32) {
+    self.history[self.head] = x;
+    self.head = (self.head + 1) % self.history.len();
+
+    for phase in self.phases.iter() {
+      let y = phase.iter().enumerate().fold(0.0,|acc,(k,h)|{
+        acc + h*self.history[(self.head + k) % self.history.len()]
+      });
+      self.peak = self.peak.max(y.abs());
+    }
+  }
+}
+
+pub struct Meter {
+  filters:Vec<[Biquad;2]>,
+  peaks:Vec<TruePeak>,
+  sub_len:usize,
+  count:usize,
+  sum:f64,
+  subs:Vec<f64>
+}
+
+impl Meter {
+  pub fn new(sample_rate:f64,channels:usize) -> Self {
+    Self{
+      filters:(0..channels).map(|_|k_weighting(sample_rate)).collect(),
+      peaks:(0..channels).map(|_|TruePeak::new(sample_rate)).collect(),
+      sub_len:(sample_rate * SUB_BLOCK_MS/1000.0).round().max(1.0) as usize,
+      count:0,
+      sum:0.0,
+      subs:vec![]
+    }
+  }
+
+  pub fn push(&mut self,frame:&[f32]) {
+    let channels = self.filters.len();
+
+    for (c,(x,(fs,tp))) in frame.iter().zip(self.filters.iter_mut().zip(self.peaks.iter_mut())).enumerate() {
+      let shelved = fs[0].process(*x);
+      let y = fs[1].process(shelved) as f64;
+      self.sum += weight(c,channels) * y*y;
+      tp.push(*x);
+    }
+
+    self.count += 1;
+    if self.count == self.sub_len {
+      self.subs.push(self.sum/self.sub_len as f64);
+      self.count = 0;
+      self.sum = 0.0;
+    }
+  }
+
+  //the mean square of each block of n sub blocks, stepping a sub block at a time
+  fn blocks(&self,n:usize) -> Vec<f64> {
+    self.subs.windows(n).map(|w|w.iter().sum::<f64>()/n as f64).collect()
+  }
+
+  pub fn finish(&self) -> Loudness {
+    let momentary = self.blocks(MOMENTARY);
+    let short = self.blocks(SHORT_TERM);
+
+    //integrated gates out silence, then anything 10 LU under the ungated average
+    let loud : Vec<f64> = momentary.iter().copied().filter(|z|lufs(*z) > ABSOLUTE_GATE).collect();
+    let mean = |zs:&[f64]|zs.iter().sum::<f64>()/zs.len() as f64;
+    let integrated = if loud.is_empty() {
+      f64::NEG_INFINITY
+    }
+    else {
+      let rel_gate = lufs(mean(&loud)) - 10.0;
+      let gated : Vec<f64> = loud.iter().copied().filter(|z|lufs(*z) > rel_gate).collect();
+      lufs(mean(&gated))
+    };
+
+    //the range is the spread between the 10th and 95th percentile of the short term
+    //loudness, after the same kind of gating with a 20 LU relative gate
+    let loud_short : Vec<f64> = short.iter().copied().filter(|z|lufs(*z) > ABSOLUTE_GATE).collect();
+    let range = if loud_short.is_empty() {
+      0.0
+    }
+    else {
+      let rel_gate = lufs(mean(&loud_short)) - 20.0;
+      let mut gated : Vec<f64> = loud_short.iter().map(|z|lufs(*z)).filter(|l|*l > rel_gate).collect();
+      gated.sort_by(|a,b|a.total_cmp(b));
+
+      let at = |p:f64|gated[((gated.len() - 1) as f64 * p).round() as usize];
+      at(0.95) - at(0.10)
+    };
+
+    let peak = self.peaks.iter().fold(0.0f32,|m,tp|m.max(tp.peak));
+    let max_of = |zs:&[f64]|zs.iter().copied().map(lufs).fold(f64::NEG_INFINITY,f64::max);
+
+    Loudness{
+      integrated,
+      range,
+      true_peak:20.0*(peak as f64).log10(),
+      momentary_max:max_of(&momentary),
+      short_term_max:max_of(&short)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f64::consts::PI;
+
+  fn sine(sr:f64,freq:f64,amp:f64,secs:f64,phase:f64) -> impl Iterator<Item=f32> {
+    (0..(sr*secs) as usize).map(move |i|(amp*(2.0*PI*freq*i as f64/sr + phase).sin()) as f32)
+  }
+
+  #[test]
+  fn test_sine_loudness() {
+    //a full scale 1k sine in one channel is -3.01 LUFS by definition
+    let mut m = Meter::new(48000.0,1);
+    sine(48000.0,997.0,1.0,5.0,0.0).for_each(|s|m.push(&[s]));
+    let l = m.finish();
+    assert!((l.integrated + 3.01).abs() < 0.1,"full scale sine should be -3 LUFS, got {}",l.integrated);
+    assert!(l.range < 0.1,"a steady sine has no range, got {}",l.range);
+
+    let mut m = Meter::new(44100.0,2);
+    sine(44100.0,997.0,0.1,5.0,0.0).for_each(|s|m.push(&[s,s]));
+    let l = m.finish();
+    assert!((l.integrated + 20.0).abs() < 0.1,"-20dB sine in stereo should be -20 LUFS, got {}",l.integrated);
+  }
+
+  #[test]
+  fn test_gating_and_range() {
+    //like the ebu tech 3342 cases, 20s at one level then 20s 10dB down
+    let mut m = Meter::new(48000.0,1);
+    let loud = sine(48000.0,997.0,0.1,20.0,0.0);
+    let quiet = sine(48000.0,997.0,0.0316,20.0,0.0);
+    loud.chain(quiet).for_each(|s|m.push(&[s]));
+    let l = m.finish();
+    assert!((l.range - 10.0).abs() < 1.0,"two levels 10dB apart should have a 10 LU range, got {}",l.range);
+
+    let mut m = Meter::new(48000.0,1);
+    let loud = sine(48000.0,997.0,0.1,10.0,0.0);
+    let silence = std::iter::repeat_n(0.0,48000*10);
+    loud.chain(silence).for_each(|s|m.push(&[s]));
+    let l = m.finish();
+    assert!((l.integrated + 23.0).abs() < 0.2,"silence shouldn't count, got {}",l.integrated);
+  }
+
+  #[test]
+  fn test_true_peak() {
+    //a quarter sample rate sine 45 degrees off never lands a sample on its peak
+    let mut m = Meter::new(48000.0,1);
+    let samples : Vec<f32> = sine(48000.0,12000.0,0.5,1.0,PI/4.0).collect();
+    let sample_peak = samples.iter().fold(0.0f32,|m,s|m.max(s.abs()));
+    samples.iter().for_each(|s|m.push(&[*s]));
+    let l = m.finish();
+
+    assert!(20.0*(sample_peak as f64).log10() < -8.9,"the samples miss the peak");
+    assert!((l.true_peak + 6.02).abs() < 0.5,"the true peak should find it, got {}",l.true_peak);
+  }
+}
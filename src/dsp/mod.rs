@@ -1,11 +1,46 @@
 mod functions;
 mod sliding_window;
 mod interpolate;
+mod dither;
+mod curve;
+mod biquad;
+mod dynamics;
+mod loudness;
+mod stretch;
+mod polyphase;
+mod fft;
+mod convolve;
+mod delay;
+mod reverb;
 
+//effects with a tail ring out until they're this quiet, or this long
+const TAIL_SILENCE : f32 = 0.001;
+const MAX_TAIL_SECS : f64 = 30.0;
+
+pub use dither::{Dither,Quantizer};
+pub use curve::Curve;
+pub use biquad::{Biquad,Filter,DEFAULT_Q};
+pub use dynamics::{Dynamics,Mode as DynamicsMode};
+pub use loudness::{Meter,Loudness};
+pub use stretch::{stretch,shift,ratio as pitch_ratio};
+pub use polyphase::{Polyphase,Quality};
+pub use convolve::convolve;
+pub use delay::Delay;
+pub use reverb::Reverb;
+
+//0 to 2 are the quick interpolators, 3 and up go through the polyphase
+//filter bank which also keeps aliasing out when downsampling
 pub fn window_resample<S:Iterator<Item=f32>>(src:S,ratio:f64,q:usize) -> Vec<f32> {
   match q {
     0 => interpolate::window(src,ratio,interpolate::lin),
     1 => interpolate::window(src,ratio,interpolate::win_sinc3),
-    _ => interpolate::window(src,ratio,interpolate::win_sinc7)
+    2 => interpolate::window(src,ratio,interpolate::win_sinc7),
+    3 => polyphase_resample(src,ratio,&Quality::default()),
+    _ => polyphase_resample(src,ratio,&Quality::best())
   }
 }
+
+pub fn polyphase_resample<S:Iterator<Item=f32>>(src:S,ratio:f64,q:&Quality) -> Vec<f32> {
+  let samples : Vec<f32> = src.collect();
+  Polyphase::new(ratio,q).process(&samples)
+}
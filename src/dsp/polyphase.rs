@@ -0,0 +1,177 @@
+use std::f64::consts::PI;
+
+//how good the converter is. passband is how much of the way to nyquist gets
+//through untouched and stopband is how far down everything past nyquist ends
+//up, the filter gets as long as those need. taps is the most it's allowed at
+//the original rate, past that the transition gets wider instead
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Quality {
+  pub taps:usize,
+  pub passband:f64,
+  pub stopband:f64
+}
+
+impl Default for Quality {
+  fn default() -> Self {
+    Self{ taps:256, passband:0.9, stopband:100.0 }
+  }
+}
+
+impl Quality {
+  pub fn best() -> Self {
+    Self{ taps:512, passband:0.95, stopband:140.0 }
+  }
+}
+
+//the fractional positions get split up this finely,
+//anything in between interpolates the two nearest
+const PHASES : usize = 512;
+
+//past this the filter gets silly big for heavy downsampling
+const MAX_TAPS : usize = 4096;
+
+//zeroth order bessel, for the kaiser window
+fn bessel_i0(x:f64) -> f64 {
+  let mut sum = 1.0;
+  let mut term = 1.0;
+  let mut k = 1.0;
+
+  while term > sum*1e-12 {
+    term *= (x/(2.0*k)).powi(2);
+    sum += term;
+    k += 1.0;
+  }
+
+  sum
+}
+
+fn kaiser_beta(atten:f64) -> f64 {
+  if atten > 50.0 {
+    0.1102*(atten - 8.7)
+  }
+  else if atten > 21.0 {
+    0.5842*(atten - 21.0).powf(0.4) + 0.07886*(atten - 21.0)
+  }
+  else {
+    0.0
+  }
+}
+
+fn sinc(x:f64) -> f64 {
+  if x == 0.0 { 1.0 } else { (PI*x).sin()/(PI*x) }
+}
+
+pub struct Polyphase {
+  bank:Vec<Vec<f32>>,
+  taps:usize,
+  ratio:f64
+}
+
+impl Polyphase {
+  //ratio is how far through the input each output sample steps, like
+  //window_resample. stepping more than one sample at a time is downsampling,
+  //so the cutoff comes down and the filter gets longer to match
+  pub fn new(ratio:f64,q:&Quality) -> Self {
+    let scale = (1.0/ratio).min(1.0);
+    let passband = q.passband.clamp(0.1,0.99);
+
+    //kaiser's estimate of the length for the attenuation over the transition,
+    //which gets narrower along with the cutoff when downsampling
+    let transition = PI*scale*(1.0 - passband);
+    let needed = ((q.stopband - 8.0).max(0.0)/(2.285*transition)).ceil() as usize;
+    let limit = (q.taps.max(4) as f64/scale).ceil() as usize;
+    let taps = ((needed.min(limit).max(4) + 1) & !1).min(MAX_TAPS);
+    let half = (taps/2) as f64;
+
+    //the cutoff sits halfway through the transition band
+    let cutoff = scale*(passband + 1.0)/2.0;
+    let beta = kaiser_beta(q.stopband);
+    let norm = bessel_i0(beta);
+
+    let bank = (0..=PHASES).map(|p|{
+      let frac = p as f64/PHASES as f64;
+
+      (0..taps).map(|j|{
+        let d = j as f64 - half + 1.0 - frac;
+        let w = (1.0 - (d/half).powi(2)).max(0.0).sqrt();
+        (cutoff*sinc(cutoff*d)*bessel_i0(beta*w)/norm) as f32
+      }).collect()
+    }).collect();
+
+    Self{ bank, taps, ratio }
+  }
+
+  //every output sample lines up with the input at n*ratio, there's no delay
+  pub fn process(&self,src:&[f32]) -> Vec<f32> {
+    let out_len = (src.len() as f64/self.ratio).ceil() as usize;
+    let half = (self.taps/2) as isize;
+
+    (0..out_len).map(|n|{
+      let pos = n as f64*self.ratio;
+      let i0 = pos.floor() as isize;
+      let ph = (pos - pos.floor())*PHASES as f64;
+      let p = (ph as usize).min(PHASES - 1);
+      let t = (ph - p as f64) as f32;
+      let (a,b) = (&self.bank[p],&self.bank[p+1]);
+
+      let first = i0 - half + 1;
+      let lo = (-first).max(0) as usize;
+      let hi = (src.len() as isize - first).clamp(0,self.taps as isize) as usize;
+
+      (lo..hi).map(|j|{
+        let k = a[j] + (b[j] - a[j])*t;
+        k*src[(first + j as isize) as usize]
+      }).sum()
+    }).collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sine(freq:f64,sr:f64,len:usize) -> Vec<f32> {
+    (0..len).map(|i|(2.0*PI*freq*i as f64/sr).sin() as f32).collect()
+  }
+
+  fn peak(sig:&[f32]) -> f32 {
+    sig.iter().fold(0.0f32,|m,s|m.max(s.abs()))
+  }
+
+  #[test]
+  fn test_downsampling_filters() {
+    let conv = Polyphase::new(2.0,&Quality::default());
+
+    //15k is past the new nyquist, it should just about disappear
+    let out = conv.process(&sine(15000.0,44100.0,20000));
+    assert_eq!(out.len(),10000,"half the rate, half the samples");
+    assert!(peak(&out[200..9800]) < 1e-4,"stuff past nyquist should be filtered, peak {}",peak(&out[200..9800]));
+
+    //just past the new nyquist is the hard part
+    for f in [11150.0,11576.0,12100.0] {
+      let out = conv.process(&sine(f,44100.0,20000));
+      assert!(peak(&out[200..9800]) < 1e-5,"{} should be filtered, peak {}",f,peak(&out[200..9800]));
+    }
+
+    //the top of the passband should come through at full level
+    let out = conv.process(&sine(0.9*11025.0,44100.0,20000));
+    assert!((peak(&out[200..9800]) - 1.0).abs() < 1e-3,"the passband should reach 0.9 of nyquist, peak {}",peak(&out[200..9800]));
+
+    //1k should come through in the right place at the right level
+    let out = conv.process(&sine(1000.0,44100.0,20000));
+    let expect = sine(1000.0,22050.0,10000);
+    let err = out[200..9800].iter().zip(expect[200..9800].iter()).fold(0.0f32,|m,(a,b)|m.max((a-b).abs()));
+    assert!(err < 1e-3,"the passband should come through untouched, error {}",err);
+  }
+
+  #[test]
+  fn test_upsampling() {
+    let conv = Polyphase::new(44100.0/96000.0,&Quality::best());
+    let out = conv.process(&sine(5000.0,44100.0,20000));
+    let expect = sine(5000.0,96000.0,out.len());
+
+    assert_eq!(out.len(),43538,"the length should follow the ratio");
+    let err = out[500..43000].iter().zip(expect[500..43000].iter()).fold(0.0f32,|m,(a,b)|m.max((a-b).abs()));
+    assert!(err < 1e-4,"upsampling should be clean, error {}",err);
+  }
+}
@@ -0,0 +1,135 @@
+use super::{TAIL_SILENCE,MAX_TAIL_SECS};
+
+//freeverb, eight damped combs in parallel into four allpasses in series.
+//the delay lengths are jezar's, tuned at 44.1k and scaled for other rates,
+//and every channel after the first gets its lines stretched a little so
+//the channels don't come out identical
+const COMBS : [usize;8] = [1116,1188,1277,1356,1422,1491,1557,1617];
+const ALLPASSES : [usize;4] = [556,441,341,225];
+const SPREAD : usize = 23;
+const FIXED_GAIN : f32 = 0.015;
+const SCALE_ROOM : f32 = 0.28;
+const OFFSET_ROOM : f32 = 0.7;
+const SCALE_DAMP : f32 = 0.4;
+
+//room, damping and mix all go from 0 to 1
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Reverb {
+  pub room:f32,
+  pub damping:f32,
+  pub mix:f32
+}
+
+impl Default for Reverb {
+  fn default() -> Self {
+    Self{ room:0.5, damping:0.5, mix:0.3 }
+  }
+}
+
+impl Reverb {
+  fn feedback(&self) -> f32 {
+    self.room.clamp(0.0,1.0)*SCALE_ROOM + OFFSET_ROOM
+  }
+
+  //the longest comb takes longest to die away
+  pub fn tail(&self,sr:f64) -> usize {
+    let longest = (COMBS[7] + SPREAD) as f64*sr/44100.0;
+    let trips = (TAIL_SILENCE.ln()/self.feedback().ln()) as f64;
+    (longest*trips).min(MAX_TAIL_SECS*sr) as usize
+  }
+
+  pub fn processor(&self,sr:f64,channels:usize) -> Freeverb {
+    let scale = |len:usize,c:usize|(((len + c*SPREAD) as f64*sr/44100.0).round() as usize).max(1);
+    let fb = self.feedback();
+    let damp = self.damping.clamp(0.0,1.0)*SCALE_DAMP;
+
+    let chans = (0..channels).map(|c|{
+      let combs = COMBS.iter().map(|l|Comb{ buf:vec![0.0;scale(*l,c)], at:0, store:0.0, fb, damp }).collect();
+      let allpasses = ALLPASSES.iter().map(|l|Allpass{ buf:vec![0.0;scale(*l,c)], at:0 }).collect();
+      (combs,allpasses)
+    }).collect();
+
+    Freeverb{ mix:self.mix, chans }
+  }
+}
+
+struct Comb {
+  buf:Vec<f32>,
+  at:usize,
+  store:f32,
+  fb:f32,
+  damp:f32
+}
+
+impl Comb {
+  fn process(&mut self,x:f32) -> f32 {
+    let out = self.buf[self.at];
+    self.store = out*(1.0 - self.damp) + self.store*self.damp;
+    self.buf[self.at] = x + self.store*self.fb;
+    self.at = (self.at + 1) % self.buf.len();
+    out
+  }
+}
+
+struct Allpass {
+  buf:Vec<f32>,
+  at:usize
+}
+
+impl Allpass {
+  fn process(&mut self,x:f32) -> f32 {
+    let held = self.buf[self.at];
+    self.buf[self.at] = x + held*0.5;
+    self.at = (self.at + 1) % self.buf.len();
+    held - x
+  }
+}
+
+pub struct Freeverb {
+  mix:f32,
+  chans:Vec<(Vec<Comb>,Vec<Allpass>)>
+}
+
+impl Freeverb {
+  //every channel hears the same mono input, they only differ in their tunings
+  pub fn process(&mut self,frame:&mut [f32]) {
+    let input = frame.iter().sum::<f32>()*FIXED_GAIN;
+
+    for (s,(combs,allpasses)) in frame.iter_mut().zip(self.chans.iter_mut()) {
+      let wet = combs.iter_mut().map(|c|c.process(input)).sum::<f32>();
+      let wet = allpasses.iter_mut().fold(wet,|w,a|a.process(w));
+      *s = *s*(1.0 - self.mix) + wet*self.mix;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_decay() {
+    let rv = Reverb{ mix:1.0, ..Default::default() };
+    let tail = rv.tail(44100.0);
+    let mut proc = rv.processor(44100.0,2);
+
+    let mut outs = vec![vec![];2];
+    for i in 0..tail {
+      let mut frame = [if i == 0 { 1.0 } else { 0.0 };2];
+      proc.process(&mut frame);
+      outs[0].push(frame[0]);
+      outs[1].push(frame[1]);
+    }
+
+    let energy = |s:&[f32]|s.iter().map(|x|x*x).sum::<f32>();
+    let early = energy(&outs[0][..tail/4]);
+    let late = energy(&outs[0][tail*3/4..]);
+
+    assert!(early > 0.0,"an impulse should make some reverb");
+    assert!(late < early*1e-4,"it should have died away by the end of the tail");
+    assert!(outs[0] != outs[1],"the channels should be decorrelated");
+
+    let big = Reverb{ room:1.0, ..rv };
+    assert!(big.tail(44100.0) > tail,"a bigger room rings longer");
+  }
+}
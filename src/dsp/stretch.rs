@@ -0,0 +1,149 @@
+use std::f32::consts::PI;
+
+//wsola, the input gets chopped into overlapping frames that get laid
+//back down at a different spacing. each frame is allowed to slide around
+//a bit to wherever it lines up best with what came before, which keeps
+//the waveform continuous without touching the pitch
+const FRAME_MS : f64 = 40.0;
+const TOLERANCE_MS : f64 = 10.0;
+
+fn hann(n:usize) -> Vec<f32> {
+  (0..n).map(|i|0.5 - 0.5*(2.0*PI*i as f32/n as f32).cos()).collect()
+}
+
+fn at(sig:&[f32],i:isize) -> f32 {
+  if i < 0 { 0.0 } else { sig.get(i as usize).copied().unwrap_or(0.0) }
+}
+
+//how well the frame at pos lines up with the template, stepping by stride
+fn correlation(sig:&[f32],template:&[f32],pos:isize,stride:usize) -> f32 {
+  template.iter().enumerate().step_by(stride).map(|(i,t)|t*at(sig,pos + i as isize)).sum()
+}
+
+//ratio is output length over input length, every channel gets the same frame
+//positions, picked by looking at all of them mixed, so they stay lined up
+pub fn stretch(channels:&[Vec<f32>],ratio:f64,sample_rate:f64) -> Vec<Vec<f32>> {
+  let in_len = channels.iter().map(|c|c.len()).max().unwrap_or(0);
+  let out_len = (in_len as f64 * ratio.max(0.0)).round() as usize;
+
+  if in_len == 0 || out_len == 0 {
+    return channels.iter().map(|_|vec![]).collect();
+  }
+
+  let n = ((sample_rate * FRAME_MS/1000.0) as usize).max(16) & !1;
+  let hop_out = n/2;
+  let hop_in = hop_out as f64/ratio;
+  let tolerance = (sample_rate * TOLERANCE_MS/1000.0) as isize;
+  let window = hann(n);
+
+  let mix : Vec<f32> = (0..in_len).map(|i|channels.iter().map(|c|c.get(i).copied().unwrap_or(0.0)).sum()).collect();
+
+  //the first frame starts half a frame early so the start of the output
+  //isn't sitting at the bottom of a window
+  let frames = out_len.div_ceil(hop_out) + 2;
+  let mut outs = vec![vec![0.0f32;frames*hop_out + n];channels.len()];
+  let mut wsum = vec![0.0f32;frames*hop_out + n];
+  let mut prev : Option<isize> = None;
+
+  for k in 0..frames {
+    let nominal = ((k as f64 - 1.0) * hop_in).round() as isize;
+
+    //what would have come next if the last frame had kept going,
+    //only the part that overlaps the new frame matters
+    let pos = match prev {
+      None => nominal,
+      Some(p) => {
+        let template : Vec<f32> = (0..hop_out).map(|i|at(&mix,p + (hop_out + i) as isize)).collect();
+        let lo = nominal - tolerance;
+        let hi = nominal + tolerance;
+
+        //coarse first, then around the best of those a sample at a time
+        let coarse = (lo..=hi).step_by(2).max_by(|a,b|{
+          correlation(&mix,&template,*a,2).total_cmp(&correlation(&mix,&template,*b,2))
+        }).unwrap_or(nominal);
+
+        ((coarse - 1).max(lo)..=(coarse + 1).min(hi)).max_by(|a,b|{
+          correlation(&mix,&template,*a,1).total_cmp(&correlation(&mix,&template,*b,1))
+        }).unwrap_or(coarse)
+      }
+    };
+
+    let out_at = k*hop_out;
+    for (c,ch) in channels.iter().enumerate() {
+      for (i,w) in window.iter().enumerate() {
+        outs[c][out_at + i] += w*at(ch,pos + i as isize);
+      }
+    }
+
+    for (i,w) in window.iter().enumerate() {
+      wsum[out_at + i] += w;
+    }
+
+    prev = Some(pos);
+  }
+
+  outs.into_iter().map(|o|{
+    o[hop_out..hop_out + out_len].iter().zip(wsum[hop_out..].iter()).map(|(s,w)|{
+      if *w > 1e-3 { s/w } else { *s }
+    }).collect()
+  }).collect()
+}
+
+//stretching by the pitch ratio and then resampling it back down to the
+//length it started at moves the pitch without moving anything else
+pub fn shift(channels:&[Vec<f32>],ratio:f64,sample_rate:f64,q:usize) -> Vec<Vec<f32>> {
+  stretch(channels,ratio,sample_rate).into_iter().zip(channels.iter()).map(|(st,ch)|{
+    let mut out = super::window_resample(st.into_iter(),ratio,q);
+    out.resize(ch.len(),0.0);
+    out
+  }).collect()
+}
+
+//semitones and cents to a frequency ratio
+pub fn ratio(semitones:f64,cents:f64) -> f64 {
+  2.0f64.powf(semitones/12.0 + cents/1200.0)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn crossings(sig:&[f32]) -> usize {
+    sig.windows(2).filter(|w|(w[0] < 0.0) != (w[1] < 0.0)).count()
+  }
+
+  #[test]
+  fn test_stretch_keeps_pitch() {
+    let sr = 44100.0;
+    let sine : Vec<f32> = (0..44100).map(|i|(2.0*PI*440.0*i as f32/sr as f32).sin()*0.5).collect();
+
+    for ratio in [0.5,1.5,2.0] {
+      let out = stretch(std::slice::from_ref(&sine),ratio,sr);
+      let len = out[0].len();
+      assert_eq!(len,(44100.0*ratio) as usize,"the length should follow the ratio");
+
+      //same number of crossings per second means the same pitch
+      let rate = crossings(&out[0]) as f64/(len as f64/sr);
+      assert!((rate - 880.0).abs() < 20.0,"{} stretch changed the pitch, {} crossings a second",ratio,rate);
+
+      let rms = (out[0][2000..len-2000].iter().map(|s|s*s).sum::<f32>()/(len-4000) as f32).sqrt();
+      assert!((rms - 0.3535).abs() < 0.03,"{} stretch changed the level, rms {}",ratio,rms);
+    }
+  }
+
+  #[test]
+  fn test_shift_keeps_length() {
+    let sr = 44100.0;
+    let sine : Vec<f32> = (0..44100).map(|i|(2.0*PI*440.0*i as f32/sr as f32).sin()*0.5).collect();
+
+    for (st,expect) in [(12.0,1760.0),(-12.0,440.0),(7.0,880.0*ratio(7.0,0.0))] {
+      let out = shift(std::slice::from_ref(&sine),ratio(st,0.0),sr,1);
+      assert_eq!(out[0].len(),sine.len(),"shifting shouldn't change the length");
+
+      let rate = crossings(&out[0][2000..42000]) as f64/(40000.0/sr);
+      assert!((rate - expect).abs() < expect*0.02,"{} semitones gave {} crossings a second",st,rate);
+    }
+
+    assert!((ratio(0.0,100.0) - ratio(1.0,0.0)).abs() < 1e-9,"a hundred cents is a semitone");
+  }
+}
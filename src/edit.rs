@@ -1,6 +1,6 @@
 use std::sync::Arc;
 use crate::{
-  snd::Snd,
+  snd::{Snd,Format,SaveOpts},
   widgets::console::Ptype,
   util::Ring
 };
@@ -18,21 +18,29 @@ pub mod channels;
 pub mod amp;
 pub mod sample_rates;
 pub mod fx;
+pub mod splice;
+pub mod zeros;
+pub mod stats;
+pub mod silence;
 
 pub struct Editor {
   stack:undo::Stack,
   dirty:bool,
   console:Ring<Ptype,20>,
-  path:Option<String>
+  path:Option<String>,
+  save_opts:SaveOpts,
+  crossfade:splice::Crossfade
 }
 
 impl Editor {
-  pub fn new(s:Arc<Snd>,file:Option<String>) -> Self {
+  pub fn new(s:Arc<Snd>,file:Option<String>,fmt:Format) -> Self {
     Self {
-      stack:undo::Stack::new(s.into()),
+      stack:undo::Stack::new(s.into(),"open".into()),
       dirty:true,
       console:Ring::new(),
-      path:file
+      path:file,
+      save_opts:SaveOpts{ format:fmt, flac_level:5, ..Default::default() },
+      crossfade:Default::default()
     }
   }
 
@@ -51,6 +59,22 @@ impl Editor {
   pub fn set_path<P:Into<Option<String>>>(&mut self,newp:P) {
     self.path = newp.into()
   }
+
+  pub fn save_opts(&self) -> SaveOpts {
+    self.save_opts
+  }
+
+  pub fn save_opts_mut(&mut self) -> &mut SaveOpts {
+    &mut self.save_opts
+  }
+
+  pub fn crossfade(&self) -> splice::Crossfade {
+    self.crossfade
+  }
+
+  pub fn crossfade_mut(&mut self) -> &mut splice::Crossfade {
+    &mut self.crossfade
+  }
   
   pub fn playback_settings(&self) -> (Arc<Snd>,f64,f64,f64,bool) {
     let ctx = self.stack.top();
@@ -88,8 +112,37 @@ impl Editor {
     self.stack.pop();
   }
 
-  pub fn push_new(&mut self,ctx:Ctx) {
-    self.stack.push(ctx);
+  pub fn redo(&mut self) -> bool {
+    self.stack.redo()
+  }
+
+  pub fn jump(&mut self,id:usize) -> bool {
+    self.stack.jump(id)
+  }
+
+  pub fn history(&self) -> Vec<undo::Entry> {
+    self.stack.entries()
+  }
+
+  pub fn history_label(&self) -> &str {
+    self.stack.label()
+  }
+
+  pub fn history_bytes(&self) -> usize {
+    self.stack.total_bytes()
+  }
+
+  pub fn undo_budget(&self) -> usize {
+    self.stack.budget()
+  }
+
+  pub fn set_undo_budget(&mut self,bytes:usize) {
+    self.stack.set_budget(bytes);
+    self.dirty = true;
+  }
+
+  pub fn push_new(&mut self,ctx:Ctx,label:String) {
+    self.stack.push(ctx,label);
     self.dirty = true;
   }
 
@@ -105,8 +158,11 @@ impl Editor {
     self.dirty = true;
   }
 
-  pub fn reset_stack(&mut self,ctx:Ctx) {
-    self.stack = undo::Stack::new(ctx);
+  //a fresh history keeps the old budget
+  pub fn reset_stack(&mut self,ctx:Ctx,label:String) {
+    let budget = self.stack.budget();
+    self.stack = undo::Stack::new(ctx,label);
+    self.stack.set_budget(budget);
     self.dirty = true;
   }
 }
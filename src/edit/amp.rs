@@ -1,5 +1,5 @@
 use super::Ctx;
-use crate::Snd;
+use crate::{Snd,blocks::Block,dsp::{Curve,Meter,Loudness}};
 
 pub fn gain(ctx:&Ctx,amt:f32) -> Ctx {
   let (s,e) = ctx.sample_region();
@@ -17,16 +17,28 @@ pub fn gain(ctx:&Ctx,amt:f32) -> Ctx {
   ctx.flip(new_snd.into())
 }
 
-pub fn lin_fade(ctx:&Ctx,start:f32,end:f32) -> Ctx {
+//ramps the gain from start to end along the curve, going down runs
+//the curve backwards so an exp fade out drops off quick and tails away
+pub fn fade(ctx:&Ctx,start:f32,end:f32,curve:Curve) -> Ctx {
   let (s,e) = ctx.sample_region();
-  let step = (end-start)/(e - s) as f32;
+  let len = (e - s).max(1) as f32;
+
+  let gain_at = |k:usize|{
+    let t = k as f32/len;
+    if end >= start {
+      start + (end - start)*curve.gain(t)
+    }
+    else {
+      end + (start - end)*curve.gain(1.0 - t)
+    }
+  };
 
   let gain_seqs = ctx.seqs().map(|(_,active,seq)|{
     if active {
-      let mut amt = start;
+      let mut k = 0;
       seq.map_rng(s..e,|sample| {
-        let out = sample * amt;
-        amt += step;
+        let out = sample * gain_at(k);
+        k += 1;
         out
       })
     }
@@ -39,6 +51,55 @@ pub fn lin_fade(ctx:&Ctx,start:f32,end:f32) -> Ctx {
   ctx.flip(new_snd.into())
 }
 
+//fades out of the selection and into the region b, whatever's between them
+//goes. the fade is as long as the shorter of the two and gets selected after
+pub fn crossfade(ctx:&Ctx,b:(usize,usize),curve:Curve) -> Option<Ctx> {
+  let (a_start,a_end) = ctx.selected_region()?.sample_range();
+  let len = ctx.snd.len();
+  let (b_start,b_end) = (b.0.min(b.1).min(len),b.1.max(b.0).min(len));
+  let n = (a_end - a_start).min(b_end - b_start);
+
+  if n == 0 {
+    return None;
+  }
+
+  let xf_seqs = ctx.seqs().map(|(_,active,seq)|{
+    if active {
+      let a = seq.samples(a_start..a_start+n);
+      let b = seq.samples(b_start..b_start+n);
+
+      let mixed = a.zip(b).enumerate().map(|(k,(a,b))|{
+        let t = (k as f32 + 0.5)/n as f32;
+        a*curve.gain(1.0 - t) + b*curve.gain(t)
+      }).collect();
+
+      seq.chunks(..a_start)
+      .chain(std::iter::once(Block::data(mixed)))
+      .chain(seq.chunks(b_start+n..))
+      .collect()
+    }
+    else {
+      seq.clone()
+    }
+  });
+
+  let new_snd = Snd::from_iter(ctx.snd.sample_rate(),xf_seqs);
+  let mut new_ctx = ctx.flip(new_snd.into());
+  new_ctx.cursor = Some(a_start as f64);
+  new_ctx.selection = Some(n as f64);
+
+  if ctx.all_channels() {
+    if b_start >= a_start {
+      new_ctx.meta.delete(a_start,b_start);
+    }
+    else {
+      new_ctx.meta.insert(a_start,a_start - b_start);
+    }
+  }
+
+  Some(new_ctx)
+}
+
 pub fn normalize(ctx:&Ctx,level:Option<f32>) -> Ctx {
   let level = level.unwrap_or(1.0);
   let (s,e) = ctx.sample_region();
@@ -56,3 +117,97 @@ pub fn normalize(ctx:&Ctx,level:Option<f32>) -> Ctx {
   let amt = level/max;
   gain(ctx,amt)
 }
+
+//runs the active channels of the selection through a bs.1770 meter
+pub fn loudness(ctx:&Ctx) -> Loudness {
+  let (s,e) = ctx.sample_region();
+  let mut chans : Vec<_> = ctx.seqs().filter(|(_,active,_)|*active).map(|(_,_,seq)|seq.samples(s..e)).collect();
+  let mut meter = Meter::new(ctx.snd.sample_rate() as f64,chans.len());
+  let mut frame = vec![0.0;chans.len()];
+
+  for _ in s..e {
+    frame.iter_mut().zip(chans.iter_mut()).for_each(|(f,c)|*f = c.next().unwrap_or(0.0));
+    meter.push(&frame);
+  }
+
+  meter.finish()
+}
+
+//gain to hit the target integrated loudness, held back if it would push the
+//true peak past the ceiling. hands back the gain in dB with the new ctx,
+//silence can't be normalized so that's None
+pub fn normalize_loudness(ctx:&Ctx,target:f64,ceiling:Option<f64>) -> Option<(Ctx,f64)> {
+  let l = loudness(ctx);
+
+  if !l.integrated.is_finite() {
+    return None;
+  }
+
+  let mut db = target - l.integrated;
+  if let Some(c) = ceiling {
+    db = db.min(c - l.true_peak);
+  }
+
+  let amt = 10f64.powf(db/20.0) as f32;
+  Some((gain(ctx,amt),db))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Arc;
+
+  fn ctx(samples:Vec<f32>) -> Ctx {
+    let snd = Snd::new(44100,vec![Block::data(samples).into()]);
+    Arc::new(snd).into()
+  }
+
+  #[test]
+  fn test_fade_curves() {
+    for c in [Curve::Linear,Curve::Exponential,Curve::Logarithmic,Curve::EqualPower,Curve::SShape] {
+      assert!(c.gain(0.0).abs() < 1e-6,"{} should start at 0",c);
+      assert!((c.gain(1.0) - 1.0).abs() < 1e-6,"{} should end at 1",c);
+      assert!(c.gain(0.25) < c.gain(0.75),"{} should go up",c);
+    }
+
+    assert!(Curve::Exponential.gain(0.5) < 0.5,"exp should be slow to start");
+    assert!(Curve::Logarithmic.gain(0.5) > 0.5,"log should be quick to start");
+
+    let mut c = ctx(vec![1.0;100]);
+    c.cursor = Some(0.0);
+    c.selection = Some(100.0);
+    let out = fade(&c,1.0,0.0,Curve::Exponential);
+    let seq = &out.snd.seqs()[0];
+    assert_eq!(seq.get_sample(&0),Some(1.0),"the fade out starts at full");
+    assert!(seq.get_sample(&50).unwrap() < 0.5,"an exp fade out drops off quick");
+  }
+
+  #[test]
+  fn test_crossfade() {
+    let mut c = ctx((0..100).map(|i|i as f32).collect());
+    c.cursor = Some(10.0);
+    c.selection = Some(10.0);
+
+    let out = crossfade(&c,(50,70),Curve::Linear).unwrap();
+    let seq = &out.snd.seqs()[0];
+    assert_eq!(seq.len(),60,"everything between the regions goes");
+    assert_eq!(seq.get_sample(&9),Some(9.0),"before the fade is the same");
+    assert_eq!(seq.get_sample(&20),Some(60.0),"after the fade carries on from the second region");
+    assert!((seq.get_sample(&10).unwrap() - 12.0).abs() < 1e-4,"the fade starts out mostly the first region");
+    assert_eq!(out.selection,Some(10.0),"the fade gets selected");
+  }
+
+  #[test]
+  fn test_normalize_loudness() {
+    let sine = (0..44100*3).map(|i|0.1*(i as f32 * 2.0*std::f32::consts::PI*997.0/44100.0).sin()).collect();
+    let c = ctx(sine);
+
+    let (out,db) = normalize_loudness(&c,-30.0,None).unwrap();
+    assert!((db + 7.0).abs() < 0.1,"a -20dB mono sine is -23 LUFS so it needs 7dB less, got {}",db);
+    assert!((loudness(&out).integrated + 30.0).abs() < 0.1,"it should hit the target");
+
+    let (_,db) = normalize_loudness(&c,0.0,Some(-6.0)).unwrap();
+    assert!((db - 14.0).abs() < 0.1,"the ceiling should hold the gain back, got {}",db);
+    assert!(normalize_loudness(&ctx(vec![0.0;44100]),-23.0,None).is_none(),"silence can't be normalized");
+  }
+}
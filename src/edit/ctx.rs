@@ -7,8 +7,9 @@ use crate::{
     Region
   },
   blocks::BlockSequence as Seq,
-  snd::Snd
+  snd::{Snd,Meta}
 };
+use super::zeros::{self,ZeroSnap};
 
 #[derive(Clone)]
 pub struct Ctx {
@@ -19,7 +20,9 @@ pub struct Ctx {
   pub channels:Mask,
   pub zoom:f64,
   pub slide:f64,
-  pub loop_mode:bool
+  pub loop_mode:bool,
+  pub zero_snap:Option<ZeroSnap>,
+  pub meta:Meta
 }
 
 impl Ctx {
@@ -58,7 +61,9 @@ impl Ctx {
       channels:self.channels,
       zoom:self.zoom,
       slide:self.slide,
-      loop_mode:self.loop_mode
+      loop_mode:self.loop_mode,
+      zero_snap:self.zero_snap,
+      meta:self.meta.clone()
     }
   }
 
@@ -92,6 +97,31 @@ impl Ctx {
     self.snd.len() as f64
   }
 
+  //markers only get moved around when an edit hits every channel,
+  //otherwise the channels that didn't change still line up with them
+  pub fn all_channels(&self) -> bool {
+    (0..self.snd.channels()).all(|c|self.channels.is_on(c))
+  }
+
+  //like "ch 0-1" or "ch 0,3", for labelling history entries
+  pub fn channel_label(&self) -> String {
+    let on : Vec<usize> = (0..self.snd.channels()).filter(|c|self.channels.is_on(*c)).collect();
+    let mut runs : Vec<(usize,usize)> = vec![];
+
+    for c in on {
+      match runs.last_mut() {
+        Some((_,e)) if *e + 1 == c => *e = c,
+        _ => runs.push((c,c))
+      }
+    }
+
+    let runs : Vec<String> = runs.iter().map(|(s,e)|{
+      if s == e { s.to_string() } else { format!("{}-{}",s,e) }
+    }).collect();
+
+    format!("ch {}",runs.join(","))
+  }
+
   pub fn seqs(&self)-> impl Iterator<Item=(usize,bool,&Seq)> {
     self.snd.seqs().iter().enumerate().map(|(i,s)|(i,self.channels.is_on(i),s))
   }
@@ -106,11 +136,23 @@ impl Ctx {
     new_snd.into()
   }
 
+  //with zero snapping on, points move to the nearest crossing
+  //if there's one within 50ms
+  pub fn snap(&self,pt:f64) -> f64 {
+    match self.zero_snap {
+      Some(zs) => {
+        let limit = self.snd.sample_rate() / 20;
+        zeros::nearest_zero(self,pt,&zs,limit).map(|z|z as f64).unwrap_or(pt)
+      },
+      None => pt
+    }
+  }
+
   pub fn default_click(&mut self,x:f64) {
     let window = self.region();
 
     let place = window.len() * x;
-    let place = place.floor() + window.start();
+    let place = self.snap(place.floor() + window.start());
 
     self.cursor = Some(place);
     self.selection = None;
@@ -120,7 +162,7 @@ impl Ctx {
     let window = self.region();
     let window_x = x * window.len();
     let window_x = window_x + window.start();
-    let window_x = window_x.floor();
+    let window_x = self.snap(window_x.floor());
     
     let new_sel = match self.cursor {
       None => None,
@@ -135,6 +177,7 @@ impl Ctx {
 impl From<Arc<Snd>> for Ctx {
   fn from(s:Arc<Snd>) -> Self {
     Self{
+      meta:s.meta().clone(),
       snd:s,
       cursor:None,
       selection:None,
@@ -142,7 +185,8 @@ impl From<Arc<Snd>> for Ctx {
       channels:Default::default(),
       zoom:1.0,
       slide:0.0,
-      loop_mode:false
+      loop_mode:false,
+      zero_snap:None
     }
   }
 }
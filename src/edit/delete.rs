@@ -1,14 +1,19 @@
 use crate::snd::Snd;
 use super::Ctx;
+use super::splice::{self,Crossfade,Splice};
 
 
 //ok this is our default delete, that looks at the selection and the channels and such
-pub fn remove_selected(ctx:&Ctx) -> Option<Ctx> {
+pub fn remove_selected(ctx:&Ctx,xf:&Crossfade) -> Option<Ctx> {
   if let Some(r) = ctx.selected_region() {
     let (s,e) = r.sample_range();
+    let fade = xf.samples(ctx.snd.sample_rate());
+
     let deld = ctx.seqs().map(|(_,active,seq)|{
       if active {
-        seq.delete(s,e)
+        let out = seq.delete(s,e);
+        let sp = Splice{ at:s, from:Some((seq,s)), to:Some((seq,e)), room:(0,out.len()) };
+        splice::join(&out,&sp,fade,xf.curve)
       }
       else {
         seq.clone()
@@ -19,6 +24,10 @@ pub fn remove_selected(ctx:&Ctx) -> Option<Ctx> {
     new_ctx.cursor=Some(s as f64);
     new_ctx.selection = None;
 
+    if ctx.all_channels() {
+      new_ctx.meta.delete(s,e);
+    }
+
     Some(new_ctx)
   }
   else {
@@ -26,13 +35,28 @@ pub fn remove_selected(ctx:&Ctx) -> Option<Ctx> {
   }
 }
 
-//aka "Crop"
-pub fn remove_non_selected(ctx:&Ctx) -> Option<Ctx> {
+//aka "Crop", the new ends of the file fade in and out of silence
+pub fn remove_non_selected(ctx:&Ctx,xf:&Crossfade) -> Option<Ctx> {
   if let Some(r) = ctx.selected_region() {
     let (s,e) = r.sample_range();
+    let fade = xf.samples(ctx.snd.sample_rate());
+
     let deld = ctx.seqs().map(|(_,active,seq)|{
       if active {
-        seq.sub_seq(s..e)
+        let mut out = seq.sub_seq(s..e);
+        let mid = out.len()/2;
+
+        if s > 0 {
+          let sp = Splice{ at:0, from:None, to:Some((seq,s)), room:(0,mid) };
+          out = splice::join(&out,&sp,fade,xf.curve);
+        }
+
+        if e < seq.len() {
+          let sp = Splice{ at:out.len(), from:Some((seq,e)), to:None, room:(mid,out.len()) };
+          out = splice::join(&out,&sp,fade,xf.curve);
+        }
+
+        out
       }
       else {
         seq.clone()
@@ -43,6 +67,10 @@ pub fn remove_non_selected(ctx:&Ctx) -> Option<Ctx> {
     new_ctx.cursor=None;
     new_ctx.selection = None;
 
+    if ctx.all_channels() {
+      new_ctx.meta.crop(s,e,ctx.snd.len());
+    }
+
     Some(new_ctx)
   }
   else {
@@ -1,7 +1,8 @@
 use super::Ctx;
 use crate::{
   snd::Snd,
-  blocks::Block
+  blocks::Block,
+  dsp::{self,Biquad,Filter,Dynamics,Delay,Reverb,DEFAULT_Q}
 };
 
 pub fn reverse(ctx:&Ctx) -> Ctx {
@@ -25,3 +26,323 @@ pub fn reverse(ctx:&Ctx) -> Ctx {
   ctx.flip(rev_snd.into())
 }
 
+
+//runs the selection of every active channel through its own processor.
+//map_rng hands over the samples in order a block at a time, so whatever
+//state the processor keeps carries right across block boundaries
+fn process<P,F>(ctx:&Ctx,mut make:F) -> Ctx
+where
+  P:FnMut(f32)->f32,
+  F:FnMut(usize)->P
+{
+  let (s,e) = ctx.sample_region();
+
+  let new_seqs = ctx.seqs().map(|(i,active,seq)|{
+    if active {
+      seq.map_rng(s..e,make(i))
+    }
+    else {
+      seq.clone()
+    }
+  });
+
+  let new_snd = Snd::from_iter(ctx.snd.sample_rate(),new_seqs);
+  ctx.flip(new_snd.into())
+}
+
+pub fn filter(ctx:&Ctx,kind:Filter,freq:f64,q:f64) -> Ctx {
+  let sr = ctx.snd.sample_rate() as f64;
+
+  process(ctx,|_|{
+    let mut bq = Biquad::new(kind,sr,freq,q);
+    move |smp|bq.process(smp)
+  })
+}
+
+pub fn invert(ctx:&Ctx) -> Ctx {
+  process(ctx,|_||smp:f32|-smp)
+}
+
+//takes the mean of each channel's selection back out of it
+pub fn remove_dc(ctx:&Ctx) -> Ctx {
+  let (s,e) = ctx.sample_region();
+
+  let offsets : Vec<f32> = ctx.seqs().map(|(_,_,seq)|{
+    let e = e.min(seq.len());
+    let n = e.saturating_sub(s).max(1) as f64;
+    (seq.samples(s..e).map(|smp|smp as f64).sum::<f64>()/n) as f32
+  }).collect();
+
+  process(ctx,|i|{
+    let dc = offsets[i];
+    move |smp|smp - dc
+  })
+}
+
+//for offsets that wander around, a highpass way down under anything audible
+pub fn remove_dc_highpass(ctx:&Ctx,freq:f64) -> Ctx {
+  filter(ctx,Filter::Highpass,freq,DEFAULT_Q)
+}
+
+//with link on every active channel gets the same gain, from the loudest
+//of them, so the stereo image doesn't wander around
+pub fn dynamics(ctx:&Ctx,dy:&Dynamics,link:bool) -> Ctx {
+  let (s,e) = ctx.sample_region();
+  let sr = ctx.snd.sample_rate() as f64;
+  let la = dy.lookahead_samples(sr);
+  let n = e - s + la;
+
+  //the detector hears lookahead samples past the selection, and silence past the end
+  let levels = |seq:&crate::blocks::BlockSequence|->Vec<f32>{
+    seq.samples(s..(e + la).min(seq.len())).map(|x|x.abs()).chain(std::iter::repeat(0.0)).take(n).collect()
+  };
+
+  let active = ctx.seqs().filter(|(_,active,_)|*active);
+
+  let curves : Vec<Vec<f32>> = if link {
+    let loudest = active.fold(vec![0.0f32;n],|mut acc,(_,_,seq)|{
+      acc.iter_mut().zip(levels(seq)).for_each(|(a,l)|*a = a.max(l));
+      acc
+    });
+
+    vec![dy.gains(sr,loudest.into_iter())]
+  }
+  else {
+    active.map(|(_,_,seq)|dy.gains(sr,levels(seq).into_iter())).collect()
+  };
+
+  let mut next_curve = 0;
+
+  let new_seqs = ctx.seqs().map(|(_,active,seq)|{
+    if active {
+      let curve = &curves[next_curve.min(curves.len() - 1)];
+      next_curve += 1;
+
+      let mut k = 0;
+      seq.map_rng(s..e,|smp|{
+        let out = smp * curve[k];
+        k += 1;
+        out
+      })
+    }
+    else {
+      seq.clone()
+    }
+  });
+
+  let new_snd = Snd::from_iter(ctx.snd.sample_rate(),new_seqs);
+  ctx.flip(new_snd.into())
+}
+
+//runs the selection through an impulse response, ir channels get handed out
+//like mix_in does, so a mono ir goes on everything. with tail the ring out
+//gets mixed over whatever comes after the selection, and the sound gets
+//longer if it runs off the end
+pub fn convolve(ctx:&Ctx,ir:&Snd,wet:f32,dry:f32,tail:bool) -> Ctx {
+  let (s,e) = ctx.sample_region();
+  let sr = ctx.snd.sample_rate();
+
+  //an ir at some other rate would come out the wrong length and colour
+  let irs : Vec<Vec<f32>> = ir.seqs().iter().map(|sq|{
+    if ir.sample_rate() == sr {
+      sq.samples(..).collect()
+    }
+    else {
+      let ratio = ir.sample_rate() as f64/sr as f64;
+      dsp::polyphase_resample(sq.samples(..),ratio,&dsp::Quality::default())
+    }
+  }).collect();
+
+  if irs.is_empty() {
+    return ctx.clone();
+  }
+
+  let ir_len = irs.iter().map(|i|i.len()).max().unwrap_or(0);
+  let tail_len = if tail { ir_len.saturating_sub(1) } else { 0 };
+  let extra = (e + tail_len).saturating_sub(ctx.snd.len());
+
+  let new_seqs = ctx.seqs().map(|(i,active,seq)|{
+    let seq = if extra > 0 {
+      seq.insert(seq.len(),&Block::silence(extra).into())
+    }
+    else {
+      seq.clone()
+    };
+
+    if active {
+      let input : Vec<f32> = seq.samples(s..e).collect();
+      let mut wet_samples = dsp::convolve(&input,&irs[i.min(irs.len()-1)],e - s + tail_len).into_iter();
+      let mut n = 0;
+
+      seq.map_rng(s..e + tail_len,|smp|{
+        let gain = if n < e - s { dry } else { 1.0 };
+        n += 1;
+        smp.mul_add(gain,wet_samples.next().unwrap_or(0.0) * wet)
+      })
+    }
+    else {
+      seq
+    }
+  });
+
+  let new_snd = Snd::from_iter(sr,new_seqs);
+  ctx.flip(new_snd.into())
+}
+
+//like process but the active channels go through together a frame at a time,
+//for effects where the channels feed into each other. tail is how much past
+//the selection to keep running on silence, what comes out there gets mixed
+//over whatever's already after the selection
+fn process_frames<P:FnMut(&mut [f32])>(ctx:&Ctx,tail:usize,mut proc:P) -> Ctx {
+  let (s,e) = ctx.sample_region();
+  let extra = (e + tail).saturating_sub(ctx.snd.len());
+
+  let seqs : Vec<_> = ctx.seqs().map(|(_,active,seq)|{
+    let seq = if extra > 0 { seq.insert(seq.len(),&Block::silence(extra).into()) } else { seq.clone() };
+    (active,seq)
+  }).collect();
+
+  let ins : Vec<Vec<f32>> = seqs.iter().filter(|(a,_)|*a).map(|(_,sq)|sq.samples(s..e + tail).collect()).collect();
+  let mut outs = ins.clone();
+  let mut frame = vec![0.0;ins.len()];
+
+  for n in 0..(e - s + tail) {
+    let in_sel = n < e - s;
+    frame.iter_mut().zip(ins.iter()).for_each(|(f,i)|*f = if in_sel { i[n] } else { 0.0 });
+    proc(&mut frame);
+
+    for (o,f) in outs.iter_mut().zip(frame.iter()) {
+      o[n] = if in_sel { *f } else { o[n] + f };
+    }
+  }
+
+  let mut outs = outs.into_iter();
+  let new_seqs = seqs.into_iter().map(|(active,seq)|{
+    match if active { outs.next() } else { None } {
+      Some(o) => seq.replace(s,e + tail,&Block::data(o).into()),
+      None => seq
+    }
+  });
+
+  let new_snd = Snd::from_iter(ctx.snd.sample_rate(),new_seqs);
+  ctx.flip(new_snd.into())
+}
+
+pub fn delay(ctx:&Ctx,d:&Delay,tail:bool) -> Ctx {
+  let sr = ctx.snd.sample_rate() as f64;
+  let mut echo = d.processor(sr,ctx.seqs().filter(|(_,a,_)|*a).count());
+  process_frames(ctx,if tail { d.tail(sr) } else { 0 },|f|echo.process(f))
+}
+
+pub fn reverb(ctx:&Ctx,rv:&Reverb,tail:bool) -> Ctx {
+  let sr = ctx.snd.sample_rate() as f64;
+  let mut verb = rv.processor(sr,ctx.seqs().filter(|(_,a,_)|*a).count());
+  process_frames(ctx,if tail { rv.tail(sr) } else { 0 },|f|verb.process(f))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Arc;
+  use crate::dsp::DEFAULT_Q;
+
+  #[test]
+  fn test_filter_across_blocks() {
+    let samples : Vec<f32> = (0..2000).map(|i|((i*7919) % 200) as f32/100.0 - 1.0).collect();
+    let split : crate::blocks::BlockSequence = [
+      Block::data(samples[..700].to_vec()),
+      Block::data(samples[700..].to_vec())
+    ].into_iter().collect();
+
+    let ctx : Ctx = Arc::new(Snd::new(44100,vec![split])).into();
+    let out = filter(&ctx,Filter::Lowpass,1000.0,DEFAULT_Q);
+
+    let mut bq = Biquad::new(Filter::Lowpass,44100.0,1000.0,DEFAULT_Q);
+    let expected : Vec<f32> = samples.iter().map(|s|bq.process(*s)).collect();
+    let got : Vec<f32> = out.snd.seqs()[0].samples(..).collect();
+
+    assert_eq!(got,expected,"filtering shouldn't notice where the blocks split");
+  }
+
+  #[test]
+  fn test_dc_and_invert() {
+    let samples : Vec<f32> = (0..4410).map(|i|0.25 + 0.5*(i as f32 * 0.1).sin()).collect();
+    let ctx : Ctx = Arc::new(Snd::new(44100,vec![Block::data(samples).into()])).into();
+
+    let mean = |c:&Ctx|c.snd.seqs()[0].samples(..).sum::<f32>()/c.snd.len() as f32;
+    assert!(mean(&remove_dc(&ctx)).abs() < 1e-4,"the mean should be gone");
+    assert!(mean(&remove_dc_highpass(&ctx,10.0)).abs() < 0.05,"the highpass should get most of it");
+
+    let flipped = invert(&ctx);
+    assert_eq!(flipped.snd.seqs()[0].get_sample(&0),Some(-0.25),"inverting flips the sign");
+  }
+
+  #[test]
+  fn test_linked_dynamics() {
+    use crate::dsp::DynamicsMode;
+
+    let loud = Block::data(vec![1.0;4800]);
+    let quiet = Block::data(vec![0.01;4800]);
+    let ctx : Ctx = Arc::new(Snd::new(48000,vec![loud.into(),quiet.into()])).into();
+    let lim = Dynamics{ attack:0.0, lookahead:1.0, ..Dynamics::new(DynamicsMode::Limiter) };
+
+    let linked = dynamics(&ctx,&lim,true);
+    let (l,r) = (linked.snd.seqs()[0].get_sample(&4000).unwrap(),linked.snd.seqs()[1].get_sample(&4000).unwrap());
+    assert!((l/r - 100.0).abs() < 1e-2,"linked channels keep their balance");
+
+    let unlinked = dynamics(&ctx,&lim,false);
+    assert_eq!(unlinked.snd.seqs()[1].get_sample(&4000),Some(0.01),"unlinked quiet channels are left alone");
+
+    //the lookahead starts turning down before the audio gets loud
+    let step = Block::data((0..4800).map(|i|if i < 2400 { 0.1 } else { 1.0 }).collect());
+    let ctx : Ctx = Arc::new(Snd::new(48000,vec![step.into()])).into();
+    let out = dynamics(&ctx,&lim,true);
+    assert!(out.snd.seqs()[0].get_sample(&2390).unwrap() < 0.1,"lookahead should get there early");
+    assert!(out.snd.seqs()[0].get_sample(&2400).unwrap() < 0.9,"nothing gets past the limiter");
+
+    //a single sample spike with the default limiter
+    let lim = Dynamics::new(DynamicsMode::Limiter);
+    let spike = Block::data((0..4800).map(|i|if i == 2400 { 1.0 } else { 0.0 }).collect());
+    let ctx : Ctx = Arc::new(Snd::new(48000,vec![spike.into()])).into();
+    let out = dynamics(&ctx,&lim,true);
+    let peak = out.snd.seqs()[0].samples(..).fold(0.0f32,|m,s|m.max(s.abs()));
+    assert!(peak <= 10f32.powf(lim.threshold/20.0) + 1e-6,"a spike shouldn't get past the limiter, got {}",peak);
+  }
+
+  #[test]
+  fn test_convolve() {
+    let click = Block::data((0..1000).map(|i|if i == 100 { 1.0 } else { 0.0 }).collect());
+    let ctx : Ctx = Arc::new(Snd::new(44100,vec![click.clone().into(),click.into()])).into();
+
+    //a two tap echo as the ir, mono so it goes on both channels
+    let ir = Snd::new(44100,vec![Block::data((0..2000).map(|i|match i { 0 => 0.5, 1500 => 0.25, _ => 0.0 }).collect()).into()]);
+
+    let out = convolve(&ctx,&ir,1.0,0.0,false);
+    assert_eq!(out.snd.len(),1000,"without the tail the length stays");
+    assert_eq!(out.snd.seqs()[1].get_sample(&100),Some(0.5),"the ir should go on every channel");
+
+    let out = convolve(&ctx,&ir,1.0,1.0,true);
+    assert_eq!(out.snd.len(),2999,"the tail should make room for itself");
+    assert_eq!(out.snd.seqs()[0].get_sample(&100),Some(1.5),"wet and dry should both be there");
+    assert!((out.snd.seqs()[0].get_sample(&1600).unwrap() - 0.25).abs() < 1e-6,"the echo should land in the tail");
+  }
+
+  #[test]
+  fn test_delay_tail() {
+    let click = Block::data((0..1000).map(|i|if i == 900 { 1.0 } else { 0.0 }).collect());
+    let after = Block::data(vec![0.1;1000]);
+    let seq : crate::blocks::BlockSequence = [click,after].into_iter().collect();
+    let mut ctx : Ctx = Arc::new(Snd::new(44100,vec![seq])).into();
+    ctx.cursor = Some(0.0);
+    ctx.selection = Some(1000.0);
+
+    let d = Delay{ feedback:0.5, mix:1.0, ..Delay::new(500.0) };
+    let cut = delay(&ctx,&d,false);
+    assert_eq!(cut.snd.seqs()[0].get_sample(&1400),Some(0.1),"without the tail nothing past the selection changes");
+
+    let rung = delay(&ctx,&d,true);
+    assert_eq!(rung.snd.seqs()[0].get_sample(&1400),Some(1.1),"the tail mixes over what comes after");
+    assert_eq!(rung.snd.seqs()[0].get_sample(&1900),Some(0.6),"and keeps repeating");
+    assert_eq!(rung.snd.len(),2000 + 500*11 - 1000,"the sound gets longer if the tail runs off the end");
+  }
+}
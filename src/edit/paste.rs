@@ -1,23 +1,38 @@
 use crate::snd::Snd;
 use super::Ctx;
 use super::util;
+use super::splice::Crossfade;
 
 //this is sorta the default "intellegent paste" that looks at your
 //selections and cursors and does things based on that.
-pub fn insert_or_replace(target:&Ctx,to_insert:&Snd) -> Ctx {
+pub fn insert_or_replace(target:&Ctx,to_insert:&Snd,xf:&Crossfade) -> Ctx {
 
   if let Some(r) = target.selected_region() {
     let (s,e) = r.into();
-    let new_snd = util::replace_multichannel(target,to_insert,(s,e));
+    let new_snd = util::replace_multichannel(target,to_insert,(s,e),xf);
     let mut new_ctx = target.flip(new_snd.into());
     new_ctx.cursor = Some(s);
     new_ctx.selection = Some(to_insert.len() as f64);
+
+    if target.all_channels() {
+      let len = target.snd.len();
+      let (rs,re) = ((s.min(e) as usize).min(len),(s.max(e) as usize).min(len));
+      new_ctx.meta.replace(rs,re,to_insert.len());
+    }
+
     return new_ctx;
   };
 
   let pt = target.cursor.unwrap_or(target.len());
-  let new_snd = util::insert_multichannel(target,to_insert,pt);
-  target.flip(new_snd.into())
+  let new_snd = util::insert_multichannel(target,to_insert,pt,xf);
+  let mut new_ctx = target.flip(new_snd.into());
+
+  if target.all_channels() {
+    let pt = (pt.floor() as usize).min(target.snd.len());
+    new_ctx.meta.insert(pt,to_insert.len());
+  }
+
+  new_ctx
 }
 
 
@@ -4,31 +4,79 @@ use crate::dsp;
 use crate::blocks::Block;
 
 pub fn resample(ctx:&Ctx,rate:f64,q:usize) -> Ctx {
+  resample_by(ctx,rate,|src,ratio|dsp::window_resample(src,ratio,q))
+}
+
+//the polyphase converter with its settings spelled out
+pub fn resample_with(ctx:&Ctx,rate:f64,q:&dsp::Quality) -> Ctx {
+  resample_by(ctx,rate,|src,ratio|dsp::polyphase_resample(src,ratio,q))
+}
+
+fn resample_by<F:Fn(Box<dyn Iterator<Item=f32> + '_>,f64) -> Vec<f32>>(ctx:&Ctx,rate:f64,f:F) -> Ctx {
   let sr = ctx.snd.sample_rate() as f64;
   let ratio = sr/rate;
 
   let new_channels = ctx.snd.seqs().iter().map(|sq|{
-    crate::blocks::Block::data(dsp::window_resample(sq.samples(..),ratio,q)).into()
+    crate::blocks::Block::data(f(Box::new(sq.samples(..)),ratio)).into()
   });
 
   let new_snd = Snd::from_iter(rate as usize,new_channels);
 
-  ctx.flip(new_snd.into())
+  //every channel gets resampled, so the markers always move
+  let mut new_ctx = ctx.flip(new_snd.into());
+  new_ctx.meta.rescale(1.0/ratio);
+  new_ctx
 }
 
 pub fn pitch(ctx:&Ctx,ratio:f64,q:usize) -> Ctx {
-  let mut out_sel_len = 0.0f64;
+  let (start,end) = ctx.sample_region();
+
+  let out = ctx.seqs().filter(|(_,active,_)|*active).map(|(_,_,seq)|{
+    dsp::window_resample(seq.sub_seq(start..end).samples(..),ratio,q)
+  }).collect();
+
+  replace_selection(ctx,out)
+}
 
+//longer or shorter without changing the pitch, the active channels
+//get stretched together so they don't drift apart
+pub fn stretch(ctx:&Ctx,ratio:f64) -> Ctx {
   let (start,end) = ctx.sample_region();
-  
+  let sr = ctx.snd.sample_rate() as f64;
+
+  let ins : Vec<Vec<f32>> = ctx.seqs().filter(|(_,active,_)|*active).map(|(_,_,seq)|{
+    seq.sub_seq(start..end).samples(..).collect()
+  }).collect();
+
+  replace_selection(ctx,dsp::stretch(&ins,ratio,sr))
+}
+
+//a pitch change that leaves the selection the same length,
+//so nothing after it moves
+pub fn shift(ctx:&Ctx,ratio:f64,q:usize) -> Ctx {
+  let (start,end) = ctx.sample_region();
+  let sr = ctx.snd.sample_rate() as f64;
+
+  let ins : Vec<Vec<f32>> = ctx.seqs().filter(|(_,active,_)|*active).map(|(_,_,seq)|{
+    seq.sub_seq(start..end).samples(..).collect()
+  }).collect();
+
+  replace_selection(ctx,dsp::shift(&ins,ratio,sr,q))
+}
+
+//swaps the selection on each active channel for the new samples, in order,
+//and makes the selection cover whatever went in
+fn replace_selection(ctx:&Ctx,out:Vec<Vec<f32>>) -> Ctx {
+  let (start,end) = ctx.sample_region();
+  let out_sel_len = out.first().map(|o|o.len()).unwrap_or(end - start) as f64;
+  let mut out = out.into_iter();
+
   let new_seqs = ctx.seqs().map(|(_,active,seq)| {
-    if active {
-      let p = Block::data(dsp::window_resample(seq.sub_seq(start..end).samples(..),ratio,q));
-      out_sel_len = p.len() as f64;
-      seq.replace(start,end,&p.into())
-    }
-    else {
-      seq.clone()
+    let new = if active { out.next() } else { None };
+
+    match new {
+      Some(o) => seq.replace(start,end,&Block::data(o).into()),
+      None => seq.clone()
     }
   });
 
@@ -46,5 +94,46 @@ pub fn pitch(ctx:&Ctx,ratio:f64,q:usize) -> Ctx {
     (_,_) => ()
   }
 
+  if ctx.all_channels() {
+    new_ctx.meta.replace(start,end,out_sel_len as usize);
+  }
+
   new_ctx
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Arc;
+
+  #[test]
+  fn test_stretch_selection() {
+    let sine : Vec<f32> = (0..10000).map(|i|(i as f32*0.05).sin()).collect();
+    let snd = Snd::new(44100,vec![Block::data(sine.clone()).into(),Block::data(sine).into()]);
+    let mut ctx : Ctx = Arc::new(snd).into();
+    ctx.channels.toggle(1);
+
+    ctx.cursor = Some(6000.0);
+    ctx.selection = Some(-4000.0);
+    let out = stretch(&ctx,1.5);
+
+    assert_eq!(out.snd.seqs()[0].len(),12000,"the active channel should get longer");
+    assert_eq!(out.snd.seqs()[1].len(),10000,"the other channel shouldn't change");
+    assert_eq!(out.selection,Some(-6000.0),"the selection should cover the stretched part");
+    assert_eq!(out.cursor,Some(8000.0),"a backwards selection should keep its end");
+  }
+
+  #[test]
+  fn test_shift_keeps_selection() {
+    let sine : Vec<f32> = (0..10000).map(|i|(i as f32*0.05).sin()).collect();
+    let snd = Snd::new(44100,vec![Block::data(sine).into()]);
+    let mut ctx : Ctx = Arc::new(snd).into();
+
+    ctx.cursor = Some(2000.0);
+    ctx.selection = Some(5000.0);
+    let out = shift(&ctx,dsp::pitch_ratio(3.0,0.0),1);
+
+    assert_eq!(out.snd.len(),10000,"the length shouldn't change");
+    assert_eq!((out.cursor,out.selection),(Some(2000.0),Some(5000.0)),"neither should the selection");
+  }
+}
@@ -0,0 +1,200 @@
+use super::Ctx;
+use crate::snd::Snd;
+
+//a stretch counts as silent when every active channel stays under the
+//threshold for at least min_len samples
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Detect {
+  pub threshold:f32,
+  pub min_len:usize
+}
+
+impl Detect {
+  pub fn new(ctx:&Ctx,threshold_db:f32,min_secs:f64) -> Self {
+    Self{
+      threshold:10f32.powf(threshold_db/20.0),
+      min_len:(min_secs.max(0.0) * ctx.snd.sample_rate() as f64).round() as usize
+    }
+  }
+}
+
+//the scan goes a few ms at a time using the summaries,
+//then the edges get found down to the sample
+const STEP_MS : usize = 5;
+
+fn quiet(ctx:&Ctx,d:&Detect,s:usize,e:usize) -> bool {
+  ctx.seqs().filter(|(_,active,_)|*active).all(|(_,_,seq)|{
+    let e = e.min(seq.len());
+    if s >= e {
+      return true;
+    }
+
+    let (min,max) = seq.summary(s,e);
+    min.abs() < d.threshold && max.abs() < d.threshold
+  })
+}
+
+//silent regions inside the selection, or the whole sound if there isn't one
+pub fn silences(ctx:&Ctx,d:&Detect) -> Vec<(usize,usize)> {
+  let (s,e) = ctx.sample_region();
+  let step = (ctx.snd.sample_rate() * STEP_MS/1000).max(1);
+  let mut runs : Vec<(usize,usize)> = vec![];
+
+  let mut at = s;
+  while at < e {
+    let nxt = (at + step).min(e);
+
+    if quiet(ctx,d,at,nxt) {
+      match runs.last_mut() {
+        Some((_,re)) if *re == at => *re = nxt,
+        _ => runs.push((at,nxt))
+      }
+    }
+
+    at = nxt;
+  }
+
+  //the loud steps on either side of a run can still have some quiet in them
+  for (rs,re) in runs.iter_mut() {
+    while *rs > s && quiet(ctx,d,*rs - 1,*rs) {
+      *rs -= 1;
+    }
+
+    while *re < e && quiet(ctx,d,*re,*re + 1) {
+      *re += 1;
+    }
+  }
+
+  runs.retain(|(rs,re)|re - rs >= d.min_len.max(1));
+  runs
+}
+
+//everything in between the silences
+pub fn sounds(ctx:&Ctx,d:&Detect) -> Vec<(usize,usize)> {
+  let (s,e) = ctx.sample_region();
+  let mut out = vec![];
+  let mut at = s;
+
+  for (rs,re) in silences(ctx,d) {
+    if rs > at {
+      out.push((at,rs));
+    }
+    at = re;
+  }
+
+  if at < e {
+    out.push((at,e));
+  }
+
+  out
+}
+
+//cuts the cuts out of the active channels, going from the back so
+//the positions in front stay put
+fn remove(ctx:&Ctx,cuts:&[(usize,usize)]) -> Ctx {
+  let new_seqs = ctx.seqs().map(|(_,active,seq)|{
+    if active {
+      cuts.iter().rev().fold(seq.clone(),|seq,(s,e)|seq.delete(*s,*e))
+    }
+    else {
+      seq.clone()
+    }
+  });
+
+  let mut new_ctx = ctx.flip(Snd::from_iter(ctx.snd.sample_rate(),new_seqs).into());
+  new_ctx.cursor = None;
+  new_ctx.selection = None;
+
+  if ctx.all_channels() {
+    for (s,e) in cuts.iter().rev() {
+      new_ctx.meta.delete(*s,*e);
+    }
+  }
+
+  new_ctx
+}
+
+//gets rid of silence at the start and end, None if there wasn't any
+pub fn trim(ctx:&Ctx,d:&Detect) -> Option<Ctx> {
+  let (s,e) = ctx.sample_region();
+  let found = silences(ctx,d);
+
+  let mut cuts : Vec<(usize,usize)> = [found.first(),found.last()].into_iter().flatten()
+  .filter(|(rs,re)|*rs == s || *re == e)
+  .copied()
+  .collect();
+
+  //one silence covering everything shows up twice
+  cuts.dedup();
+
+  if cuts.is_empty() {
+    None
+  }
+  else {
+    Some(remove(ctx,&cuts))
+  }
+}
+
+//gaps in the middle longer than max_len get cut down to it, leaving
+//half of what's kept on either side so the pause still sounds natural
+pub fn shorten_gaps(ctx:&Ctx,d:&Detect,max_len:usize) -> Option<Ctx> {
+  let (s,e) = ctx.sample_region();
+
+  let cuts : Vec<(usize,usize)> = silences(ctx,d).into_iter()
+  .filter(|(rs,re)|*rs > s && *re < e && re - rs > max_len)
+  .map(|(rs,re)|(rs + max_len/2,re - (max_len - max_len/2)))
+  .collect();
+
+  if cuts.is_empty() {
+    None
+  }
+  else {
+    Some(remove(ctx,&cuts))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Arc;
+  use crate::blocks::Block;
+
+  fn ctx() -> Ctx {
+    //half a second of quiet, a second of sound, a second of quiet,
+    //a second of sound and a quarter second of quiet
+    let mut samples = vec![0.0001;22050];
+    samples.extend(vec![0.5;44100]);
+    samples.extend(vec![0.0;44100]);
+    samples.extend(vec![-0.5;44100]);
+    samples.extend(vec![0.0;11025]);
+
+    let snd = Snd::new(44100,vec![Block::data(samples).into()]);
+    Arc::new(snd).into()
+  }
+
+  #[test]
+  fn test_silences() {
+    let c = ctx();
+    let d = Detect::new(&c,-60.0,0.1);
+
+    assert_eq!(silences(&c,&d),vec![(0,22050),(66150,110250),(154350,165375)],"the silences should be found to the sample");
+    assert_eq!(sounds(&c,&d),vec![(22050,66150),(110250,154350)],"sounds are what's in between");
+
+    let long = Detect::new(&c,-60.0,0.6);
+    assert_eq!(silences(&c,&long),vec![(66150,110250)],"short silences don't count");
+  }
+
+  #[test]
+  fn test_trim_and_shorten() {
+    let c = ctx();
+    let d = Detect::new(&c,-60.0,0.1);
+
+    let trimmed = trim(&c,&d).unwrap();
+    assert_eq!(trimmed.snd.len(),132300,"the ends should be gone");
+    assert_eq!(trimmed.snd.seqs()[0].get_sample(&0),Some(0.5),"it starts with the sound");
+
+    let shortened = shorten_gaps(&c,&d,4410).unwrap();
+    assert_eq!(shortened.snd.len(),165375 - 44100 + 4410,"the gap should be a tenth of a second");
+    assert_eq!(shortened.snd.seqs()[0].get_sample(&(66150 + 4410)),Some(-0.5),"the second sound comes right after");
+  }
+}
@@ -0,0 +1,107 @@
+use crate::blocks::BlockSequence as Seq;
+use crate::dsp::Curve;
+
+//a short crossfade laid over each edit point so splices don't click,
+//0 ms leaves them butted together like they've always been
+#[derive(Debug,Clone,Copy,PartialEq,Default)]
+pub struct Crossfade {
+  pub ms:f64,
+  pub curve:Curve
+}
+
+impl Crossfade {
+  pub fn samples(&self,sample_rate:usize) -> usize {
+    (self.ms.max(0.0) * sample_rate as f64 / 1000.0).round() as usize
+  }
+}
+
+//one edit point in a seq that's been spliced together. left of `at` it's `from`
+//playing up to its index, right of it it's `to` playing on from its index,
+//None is silence for the ends of the file. room is the part of the seq the
+//fade can use without running into the next splice
+pub struct Splice<'a> {
+  pub at:usize,
+  pub from:Option<(&'a Seq,usize)>,
+  pub to:Option<(&'a Seq,usize)>,
+  pub room:(usize,usize)
+}
+
+//how much a source has before and after its index
+fn reach(src:Option<(&Seq,usize)>) -> (usize,usize) {
+  match src {
+    Some((seq,i)) => (i.min(seq.len()),seq.len().saturating_sub(i)),
+    None => (usize::MAX,usize::MAX)
+  }
+}
+
+fn grab(src:Option<(&Seq,usize)>,before:usize,after:usize) -> Vec<f32> {
+  match src {
+    Some((seq,i)) => seq.samples(i-before..i+after).collect(),
+    None => vec![0.0;before+after]
+  }
+}
+
+//the fade keeps the length the same, it lets whatever was cut off carry
+//on for a bit on either side of the splice and fades between them. it
+//gets centered on the splice when there's material on both sides,
+//otherwise it slides over to wherever there is
+pub fn join(out:&Seq,sp:&Splice,len:usize,curve:Curve) -> Seq {
+  let (from_pre,from_post) = reach(sp.from);
+  let (to_pre,to_post) = reach(sp.to);
+
+  let max_before = from_pre.min(to_pre).min(sp.at.saturating_sub(sp.room.0));
+  let max_after = from_post.min(to_post)
+  .min(sp.room.1.saturating_sub(sp.at))
+  .min(out.len().saturating_sub(sp.at));
+
+  let before = (len/2).min(max_before);
+  let after = (len - before).min(max_after);
+  let before = (len - after).min(max_before);
+
+  if before + after < 2 {
+    return out.clone();
+  }
+
+  let from = grab(sp.from,before,after);
+  let to = grab(sp.to,before,after);
+  let n = (before + after) as f32;
+  let mut k = 0;
+
+  out.map_rng(sp.at-before..sp.at+after,|_|{
+    let t = (k as f32 + 0.5)/n;
+    let smp = from[k]*curve.gain(1.0 - t) + to[k]*curve.gain(t);
+    k += 1;
+    smp
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::blocks::Block;
+
+  #[test]
+  fn test_join() {
+    let seq : Seq = Block::data((0..100).map(|i|i as f32).collect()).into();
+    let out = seq.delete(40,60);
+    let sp = Splice{ at:40, from:Some((&seq,40)), to:Some((&seq,60)), room:(0,out.len()) };
+    let faded = join(&out,&sp,10,Curve::Linear);
+
+    assert_eq!(faded.len(),80,"the fade shouldn't change the length");
+    assert_eq!(faded.get_sample(&34),Some(34.0),"nothing before the fade changes");
+    assert_eq!(faded.get_sample(&45),Some(65.0),"nothing after the fade changes");
+
+    //the deleted part carries on under the fade, 40 fades towards 60
+    let mid = faded.get_sample(&40).unwrap();
+    assert!((mid - 51.0).abs() < 1e-4,"the middle of the fade is about halfway between, got {}",mid);
+
+    let sp = Splice{ at:0, from:None, to:Some((&seq,0)), room:(0,50) };
+    let faded = join(&seq,&sp,10,Curve::Linear);
+    assert_eq!(faded.get_sample(&0),Some(0.0),"fading in from silence");
+    assert!(faded.get_sample(&9).unwrap() < 9.0,"the fade in should be under the original");
+    assert_eq!(faded.get_sample(&10),Some(10.0),"the fade in should end");
+
+    let off = join(&out,&Splice{ at:40, from:Some((&seq,40)), to:Some((&seq,60)), room:(0,80) },0,Curve::Linear);
+    assert_eq!(off.get_sample(&40),Some(60.0),"no fade is a butt splice");
+  }
+}
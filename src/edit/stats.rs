@@ -0,0 +1,95 @@
+use super::Ctx;
+
+//levels are linear, crest is peak over rms and zero crossings are per second
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Stats {
+  pub channel:usize,
+  pub min:f32,
+  pub max:f32,
+  pub peak:f32,
+  pub rms:f32,
+  pub dc:f32,
+  pub crest:f32,
+  pub clipped:usize,
+  pub zero_rate:f32
+}
+
+//anything at or past full scale counts as clipped
+const CLIP : f32 = 1.0;
+
+//the peaks come straight out of the summaries, the rest needs a pass
+//over the samples. clipping only gets counted if the summary says there is some
+pub fn stats(ctx:&Ctx) -> Vec<Stats> {
+  let (s,e) = ctx.sample_region();
+  let secs = (e - s) as f32/ctx.snd.sample_rate() as f32;
+
+  ctx.seqs().filter(|(_,active,_)|*active).map(|(channel,_,seq)|{
+    let e = e.min(seq.len());
+    let (min,max) = if e > s { seq.summary(s,e) } else { (0.0,0.0) };
+    let peak = min.abs().max(max.abs());
+    let might_clip = peak >= CLIP;
+
+    let mut sum = 0.0f64;
+    let mut sum_sq = 0.0f64;
+    let mut clipped = 0;
+    let mut crossings = 0;
+    let mut last = 0.0f32;
+
+    for (i,smp) in seq.samples(s..e).enumerate() {
+      sum += smp as f64;
+      sum_sq += (smp as f64)*(smp as f64);
+
+      if might_clip && smp.abs() >= CLIP {
+        clipped += 1;
+      }
+
+      if i > 0 && (last < 0.0) != (smp < 0.0) {
+        crossings += 1;
+      }
+      last = smp;
+    }
+
+    let n = (e.saturating_sub(s)).max(1) as f64;
+    let rms = (sum_sq/n).sqrt() as f32;
+
+    Stats{
+      channel,
+      min,
+      max,
+      peak,
+      rms,
+      dc:(sum/n) as f32,
+      crest:if rms > 0.0 { peak/rms } else { 0.0 },
+      clipped,
+      zero_rate:if secs > 0.0 { crossings as f32/secs } else { 0.0 }
+    }
+  }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Arc;
+  use crate::{snd::Snd,blocks::Block};
+
+  #[test]
+  fn test_stats() {
+    //a square wave at 100Hz with a dc offset and a couple of clipped samples
+    let mut samples : Vec<f32> = (0..44100).map(|i|if (i/220) % 2 == 0 { 0.6 } else { -0.4 }).collect();
+    samples[10] = 1.0;
+    samples[11] = 1.0;
+
+    let snd = Snd::new(44100,vec![Block::data(samples).into(),Block::silence(44100).into()]);
+    let ctx : Ctx = Arc::new(snd).into();
+    let st = stats(&ctx);
+
+    assert_eq!(st.len(),2,"a set of stats per channel");
+    assert_eq!(st[0].peak,1.0,"the peak comes from the summary");
+    assert_eq!(st[0].clipped,2,"clipped samples get counted");
+    assert!((st[0].dc - 0.1).abs() < 0.01,"the dc offset is the mean, got {}",st[0].dc);
+    assert!((st[0].zero_rate - 200.0).abs() < 2.0,"a 100Hz square crosses 200 times a second, got {}",st[0].zero_rate);
+    assert!(st[0].crest > 1.0,"peak over rms");
+    assert_eq!(st[1].rms,0.0,"silence has no rms");
+    assert_eq!(st[1].crest,0.0,"silence has no crest either");
+  }
+}
@@ -1,28 +1,300 @@
+use std::collections::HashMap;
 use super::Ctx;
 
+//the history is a tree, undoing and then doing something new starts
+//a new branch instead of throwing the old one away.
+//ids stay the same even when old entries get pruned
+struct Node {
+  id:usize,
+  label:String,
+  ctx:Ctx,
+  parent:Option<usize>,
+  //redo follows whichever child was visited last
+  redo:Option<usize>,
+  //the blocks this entry holds, worked out once when it goes in
+  storage:HashMap<usize,usize>
+}
+
+//what the lua side gets to see about each entry, bytes is the
+//sample data that only this entry is holding onto
+#[derive(Debug,Clone)]
+pub struct Entry {
+  pub id:usize,
+  pub label:String,
+  pub parent:Option<usize>,
+  pub current:bool,
+  pub bytes:usize
+}
+
+//a gig of samples is plenty of undo
+pub const DEFAULT_BUDGET : usize = 1024 * 1024 * 1024;
+
 pub struct Stack {
-  btm:Ctx,
-  undos:Vec<Ctx>
+  nodes:Vec<Node>,
+  current:usize,
+  next_id:usize,
+  budget:usize,
+  //every block any entry holds, its size and how many entries hold it,
+  //and the total of all of them. kept up to date as entries come and go
+  owners:HashMap<usize,(usize,usize)>,
+  total:usize
+}
+
+fn storage(ctx:&Ctx) -> HashMap<usize,usize> {
+  ctx.snd.seqs().iter().flat_map(|s|s.storage()).collect()
 }
 
 impl Stack {
-  pub fn new(btm:Ctx) -> Self {
-    Self{ btm, undos:vec![] }
+  pub fn new(btm:Ctx,label:String) -> Self {
+    let mut stack = Self{
+      nodes:vec![],
+      current:0,
+      next_id:1,
+      budget:DEFAULT_BUDGET,
+      owners:HashMap::new(),
+      total:0
+    };
+
+    stack.add(Node{ id:0, label, storage:storage(&btm), ctx:btm, parent:None, redo:None });
+    stack
+  }
+
+  fn add(&mut self,node:Node) {
+    for (at,bytes) in node.storage.iter() {
+      let owner = self.owners.entry(*at).or_insert((*bytes,0));
+      if owner.1 == 0 {
+        self.total += bytes;
+      }
+      owner.1 += 1;
+    }
+
+    self.nodes.push(node);
+  }
+
+  fn remove(&mut self,i:usize) -> Node {
+    let node = self.nodes.remove(i);
+
+    for at in node.storage.keys() {
+      if let Some(owner) = self.owners.get_mut(at) {
+        owner.1 -= 1;
+        if owner.1 == 0 {
+          self.total -= owner.0;
+          self.owners.remove(at);
+        }
+      }
+    }
+
+    node
+  }
+
+  fn idx(&self,id:usize) -> Option<usize> {
+    self.nodes.binary_search_by_key(&id,|n|n.id).ok()
   }
 
-  pub fn push(&mut self, new:Ctx) {
-    self.undos.push(new);
+  fn node(&self,id:usize) -> &Node {
+    &self.nodes[self.idx(id).unwrap()]
+  }
+
+  fn node_mut(&mut self,id:usize) -> &mut Node {
+    let i = self.idx(id).unwrap();
+    &mut self.nodes[i]
+  }
+
+  pub fn push(&mut self,new:Ctx,label:String) {
+    let id = self.next_id;
+    self.next_id += 1;
+
+    self.add(Node{ id, label, storage:storage(&new), ctx:new, parent:Some(self.current), redo:None });
+    self.node_mut(self.current).redo = Some(id);
+    self.current = id;
+    self.prune();
   }
 
   pub fn pop(&mut self) {
-    self.undos.pop();
+    if let Some(p) = self.node(self.current).parent {
+      self.current = p;
+    }
+  }
+
+  pub fn redo(&mut self) -> bool {
+    match self.node(self.current).redo {
+      Some(next) => {
+        self.current = next;
+        true
+      },
+      None => false
+    }
+  }
+
+  //jumping somewhere makes that the path redo follows on the way back down
+  pub fn jump(&mut self,id:usize) -> bool {
+    if self.idx(id).is_none() {
+      return false;
+    }
+
+    let mut child = id;
+    while let Some(p) = self.node(child).parent {
+      self.node_mut(p).redo = Some(child);
+      child = p;
+    }
+
+    self.current = id;
+    true
+  }
+
+  pub fn budget(&self) -> usize {
+    self.budget
+  }
+
+  pub fn set_budget(&mut self,bytes:usize) {
+    self.budget = bytes;
+    self.prune();
+  }
+
+  //oldest entries go first, whatever's current always stays.
+  //anything hanging off a pruned entry moves up to its parent
+  fn prune(&mut self) {
+    while self.total > self.budget {
+      let Some(i) = self.nodes.iter().position(|n|n.id != self.current) else {
+        return
+      };
+
+      let gone = self.remove(i);
+
+      for n in self.nodes.iter_mut() {
+        if n.parent == Some(gone.id) {
+          n.parent = gone.parent;
+        }
+
+        if n.redo == Some(gone.id) {
+          n.redo = gone.redo;
+        }
+      }
+    }
+  }
+
+  pub fn entries(&self) -> Vec<Entry> {
+    //what only this entry holds, blocks shared between entries don't count
+    self.nodes.iter().map(|n|{
+      let bytes = n.storage.keys().filter(|at|self.owners[*at].1 == 1).map(|at|self.owners[at].0).sum();
+      Entry{ id:n.id, label:n.label.clone(), parent:n.parent, current:n.id == self.current, bytes }
+    }).collect()
+  }
+
+  pub fn total_bytes(&self) -> usize {
+    self.total
+  }
+
+  pub fn label(&self) -> &str {
+    &self.node(self.current).label
   }
 
   pub fn top(&self) -> &Ctx {
-    self.undos.last().unwrap_or(&self.btm)
+    &self.node(self.current).ctx
   }
 
+  //for the cursor and selection and such, swapping the snd out
+  //from under an entry would throw off the storage counts
   pub fn top_mut(&mut self) -> &mut Ctx {
-    self.undos.last_mut().unwrap_or(&mut self.btm)
+    let cur = self.current;
+    &mut self.node_mut(cur).ctx
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Arc;
+  use crate::{snd::Snd,blocks::Block};
+
+  fn ctx(len:usize) -> Ctx {
+    let snd = Snd::new(44100,vec![Block::silence(len).into()]);
+    Arc::new(snd).into()
+  }
+
+  fn data(len:usize) -> Ctx {
+    let snd = Snd::new(44100,vec![Block::data(vec![0.0;len]).into()]);
+    Arc::new(snd).into()
+  }
+
+  #[test]
+  fn test_branching() {
+    let mut stack = Stack::new(ctx(0),"open".into());
+    stack.push(ctx(1),"one".into());
+    stack.push(ctx(2),"two".into());
+
+    stack.pop();
+    assert_eq!(stack.top().snd.len(),1,"undo should go back one");
+    assert!(stack.redo(),"there should be something to redo");
+    assert_eq!(stack.top().snd.len(),2,"redo should come back");
+    assert!(!stack.redo(),"nothing to redo at the end");
+
+    stack.pop();
+    stack.push(ctx(3),"three".into());
+    assert_eq!(stack.entries().len(),4,"the old branch should still be there");
+
+    stack.pop();
+    stack.redo();
+    assert_eq!(stack.top().snd.len(),3,"redo follows the newest branch");
+
+    assert!(stack.jump(2),"the old branch should be reachable");
+    assert_eq!(stack.top().snd.len(),2,"jumping should land on the old branch");
+    assert_eq!(stack.label(),"two","entries keep their labels");
+
+    stack.pop();
+    stack.redo();
+    assert_eq!(stack.top().snd.len(),2,"after a jump redo follows that branch");
+
+    stack.pop();
+    stack.pop();
+    stack.pop();
+    assert_eq!(stack.top().snd.len(),0,"undo stops at the bottom");
+    assert!(!stack.jump(10),"can't jump to entries that don't exist");
+  }
+
+  #[test]
+  fn test_pruning() {
+    let mut stack = Stack::new(data(1000),"open".into());
+    let per_entry = stack.total_bytes();
+    stack.set_budget(per_entry * 3);
+
+    let shared = data(1000);
+    stack.push(shared.clone(),"a".into());
+    stack.push(shared.clone(),"b".into());
+    assert_eq!(stack.entries().len(),3,"shared data only counts once");
+    assert_eq!(stack.entries()[1].bytes,0,"shared data isn't unique to anybody");
+
+    stack.push(data(1000),"c".into());
+    stack.push(data(1000),"d".into());
+
+    let entries = stack.entries();
+    assert!(stack.total_bytes() <= per_entry * 3,"the budget should be kept");
+    let fresh : HashMap<usize,usize> = stack.nodes.iter().flat_map(|n|storage(&n.ctx)).collect();
+    assert_eq!(stack.total_bytes(),fresh.values().sum::<usize>(),"the running total should match a recount");
+    assert_eq!(entries.len(),4,"only what's needed to get under budget goes");
+    assert_eq!(entries[0].label,"a","the oldest entries should go first");
+    assert_eq!(entries[0].parent,None,"the oldest entry left becomes the bottom");
+    assert_eq!(stack.label(),"d","the current entry never gets pruned");
+
+    stack.pop();
+    stack.pop();
+    stack.pop();
+    assert_eq!(stack.label(),"a","undo stops at whatever's left");
+
+    //pruning an old branch shouldn't move redo off the one we were on
+    let mut stack = Stack::new(data(1000),"open".into());
+    stack.push(data(1000),"old".into());
+    stack.push(data(1000),"old child".into());
+    stack.pop();
+    stack.pop();
+    stack.push(data(1000),"new".into());
+    stack.push(data(1000),"new child".into());
+    stack.pop();
+    stack.pop();
+
+    stack.set_budget(per_entry * 4);
+    assert_eq!(stack.entries().len(),4,"just the old branch's first entry should go");
+    assert!(stack.redo(),"there should still be something to redo");
+    assert_eq!(stack.label(),"new","redo should stay on the branch we were on");
   }
 }
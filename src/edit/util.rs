@@ -1,10 +1,12 @@
-use crate::snd::Snd;
+use crate::{snd::Snd,blocks::BlockSequence as Seq};
 use super::Ctx;
+use super::splice::{self,Crossfade,Splice};
 
-pub fn insert_multichannel(ctx:&Ctx,to_insert:&Snd,point:f64) -> Snd {
+pub fn insert_multichannel(ctx:&Ctx,to_insert:&Snd,point:f64,xf:&Crossfade) -> Snd {
   //inserts need to occur at sample bounds
   let insert_point = point.floor() as usize;
   let insert_point = insert_point.min(ctx.snd.len());
+  let fade = xf.samples(ctx.snd.sample_rate());
 
   let new_seqs = ctx.seqs().map(|(n,active,main_seq)|{
     if active {
@@ -13,7 +15,8 @@ pub fn insert_multichannel(ctx:&Ctx,to_insert:&Snd,point:f64) -> Snd {
       //thought about it
       let insert_idx = n.min(to_insert.channels()-1);
       let insert_sequence = to_insert.channel(insert_idx).map(|iseq|{
-        main_seq.insert(insert_point,iseq)
+        let out = main_seq.insert(insert_point,iseq);
+        fade_in_and_out(out,main_seq,iseq,insert_point,insert_point,fade,xf)
       });
 
       insert_sequence.unwrap_or(main_seq.clone())
@@ -27,13 +30,14 @@ pub fn insert_multichannel(ctx:&Ctx,to_insert:&Snd,point:f64) -> Snd {
   Snd::from_iter(ctx.snd.sample_rate(),new_seqs)
 }
 
-pub fn replace_multichannel(ctx:&Ctx,to_insert:&Snd,region:(f64,f64)) -> Snd {
+pub fn replace_multichannel(ctx:&Ctx,to_insert:&Snd,region:(f64,f64),xf:&Crossfade) -> Snd {
   //the region needs to be set to sample boundaries and trimmed to the length
   //of the main sound
   let (start,end) = region;
   let (start,end) = (start.min(end),end.max(start));
   let (start,end) = (start as usize,end as usize);
   let (start,end) = (start.min(ctx.snd.len()),end.min(ctx.snd.len()));
+  let fade = xf.samples(ctx.snd.sample_rate());
 
   let new_seqs = ctx.seqs().map(|(n,active,main_seq)|{
     if active {
@@ -42,7 +46,8 @@ pub fn replace_multichannel(ctx:&Ctx,to_insert:&Snd,region:(f64,f64)) -> Snd {
       //thought about it
       let insert_idx = n.min(to_insert.channels() - 1);
       let insert_sequence = to_insert.channel(insert_idx).map(|iseq|{
-        main_seq.replace(start,end,iseq)
+        let out = main_seq.replace(start,end,iseq);
+        fade_in_and_out(out,main_seq,iseq,start,end,fade,xf)
       });
 
       insert_sequence.unwrap_or(main_seq.clone())
@@ -56,3 +61,23 @@ pub fn replace_multichannel(ctx:&Ctx,to_insert:&Snd,region:(f64,f64)) -> Snd {
   Snd::from_iter(ctx.snd.sample_rate(),new_seqs)
 }
 
+
+//crossfades both edges of something that got put in over start..end,
+//edges at the ends of the file don't need it
+fn fade_in_and_out(out:Seq,main_seq:&Seq,iseq:&Seq,start:usize,end:usize,fade:usize,xf:&Crossfade) -> Seq {
+  let mid = start + iseq.len()/2;
+  let mut out = out;
+
+  if start > 0 {
+    let sp = Splice{ at:start, from:Some((main_seq,start)), to:Some((iseq,0)), room:(0,mid) };
+    out = splice::join(&out,&sp,fade,xf.curve);
+  }
+
+  if end < main_seq.len() {
+    let at = start + iseq.len();
+    let sp = Splice{ at, from:Some((iseq,iseq.len())), to:Some((main_seq,end)), room:(mid,out.len()) };
+    out = splice::join(&out,&sp,fade,xf.curve);
+  }
+
+  out
+}
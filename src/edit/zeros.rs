@@ -0,0 +1,145 @@
+use super::Ctx;
+
+//which crossings count when snapping, with no channel it looks at
+//the active channels mixed together
+#[derive(Debug,Clone,Copy,Default,PartialEq)]
+pub struct ZeroSnap {
+  pub rising:bool,
+  pub channel:Option<usize>
+}
+
+impl ZeroSnap {
+  fn is_crossing(&self,a:f32,b:f32) -> bool {
+    if self.rising {
+      a < 0.0 && b >= 0.0
+    }
+    else {
+      //landing on zero counts, sitting on it doesn't, or silence would be all crossings
+      (b == 0.0 && a != 0.0) || (a < 0.0) != (b < 0.0)
+    }
+  }
+}
+
+//searching goes a chunk at a time so a long stretch without
+//a crossing doesn't mean pulling in the whole file at once
+const CHUNK : usize = 4096;
+
+//nothing if there aren't any channels to look at
+fn signal(ctx:&Ctx,snap:&ZeroSnap,s:usize,e:usize) -> Option<Vec<f32>> {
+  let mut out = vec![0.0;e-s];
+  let mut any = false;
+
+  for (i,active,seq) in ctx.seqs() {
+    let used = match snap.channel {
+      Some(c) => c == i,
+      None => active
+    };
+
+    if used {
+      out.iter_mut().zip(seq.samples(s..e)).for_each(|(o,smp)|*o += smp);
+      any = true;
+    }
+  }
+
+  any.then_some(out)
+}
+
+//crossings sit on the boundary between the two samples, which is the
+//index of the second one. limit is how far to look before giving up
+pub fn next_zero(ctx:&Ctx,pos:f64,snap:&ZeroSnap,limit:usize) -> Option<usize> {
+  let first = pos.max(0.0).floor() as usize + 1;
+  let last = ctx.snd.len().min(first.saturating_add(limit));
+  let mut i = first;
+
+  while i < last {
+    let end = (i + CHUNK).min(last);
+    let sig = signal(ctx,snap,i-1,end)?;
+
+    if let Some(k) = sig.windows(2).position(|w|snap.is_crossing(w[0],w[1])) {
+      return Some(i + k);
+    }
+
+    i = end;
+  }
+
+  None
+}
+
+pub fn previous_zero(ctx:&Ctx,pos:f64,snap:&ZeroSnap,limit:usize) -> Option<usize> {
+  let last = (pos.max(0.0).ceil() as usize).min(ctx.snd.len());
+  let first = last.saturating_sub(limit).max(1);
+  let mut end = last;
+
+  while end > first {
+    let start = end.saturating_sub(CHUNK).max(first);
+    let sig = signal(ctx,snap,start-1,end)?;
+
+    if let Some(k) = sig.windows(2).rposition(|w|snap.is_crossing(w[0],w[1])) {
+      return Some(start + k);
+    }
+
+    end = start;
+  }
+
+  None
+}
+
+//pos itself counts, ties go to the earlier one
+pub fn nearest_zero(ctx:&Ctx,pos:f64,snap:&ZeroSnap,limit:usize) -> Option<usize> {
+  let nxt = next_zero(ctx,pos,snap,limit);
+  let reach = nxt.map(|n|(n as f64 - pos).ceil() as usize + 1).unwrap_or(limit);
+  let prev = previous_zero(ctx,pos.floor() + 1.0,snap,reach);
+
+  match (prev,nxt) {
+    (Some(p),Some(n)) if pos - p as f64 <= n as f64 - pos => Some(p),
+    (Some(_),Some(n)) => Some(n),
+    (p,n) => p.or(n)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Arc;
+  use crate::{snd::Snd,blocks::Block};
+
+  #[test]
+  fn test_zeros() {
+    //crosses going up at 2, down at 5, and up again at 8
+    let left = vec![-1.0,-0.5,0.5,1.0,0.5,-0.5,-1.0,-0.5,0.5,1.0];
+    let right = vec![1.0;10];
+    let snd = Snd::new(44100,vec![Block::data(left).into(),Block::data(right).into()]);
+    let mut ctx : Ctx = Arc::new(snd).into();
+    ctx.channels.toggle(1);
+
+    let any = ZeroSnap::default();
+    let rising = ZeroSnap{ rising:true, channel:None };
+
+    assert_eq!(next_zero(&ctx,0.0,&any,usize::MAX),Some(2),"next crossing");
+    assert_eq!(next_zero(&ctx,2.0,&any,usize::MAX),Some(5),"next starts after pos");
+    assert_eq!(next_zero(&ctx,2.0,&rising,usize::MAX),Some(8),"rising only skips falling ones");
+    assert_eq!(previous_zero(&ctx,8.0,&any,usize::MAX),Some(5),"previous ends before pos");
+    assert_eq!(previous_zero(&ctx,7.0,&rising,usize::MAX),Some(2),"previous rising");
+    assert_eq!(nearest_zero(&ctx,4.0,&any,usize::MAX),Some(5),"nearest going forward");
+    assert_eq!(nearest_zero(&ctx,5.0,&any,usize::MAX),Some(5),"nearest can be right there");
+    assert_eq!(nearest_zero(&ctx,3.0,&any,usize::MAX),Some(2),"nearest going back");
+    assert_eq!(next_zero(&ctx,5.0,&any,2),None,"the limit stops the search");
+
+    let right_only = ZeroSnap{ rising:false, channel:Some(1) };
+    assert_eq!(next_zero(&ctx,0.0,&right_only,usize::MAX),None,"a channel that never crosses");
+  }
+
+  #[test]
+  fn test_nothing_to_snap_to() {
+    let snd = Snd::new(44100,vec![Block::data(vec![0.0;100]).into(),Block::data(vec![-1.0,1.0].repeat(50)).into()]);
+    let mut ctx : Ctx = Arc::new(snd).into();
+    ctx.channels.toggle(1);
+
+    let any = ZeroSnap::default();
+    assert_eq!(next_zero(&ctx,10.0,&any,usize::MAX),None,"silence isn't full of crossings");
+    assert_eq!(next_zero(&ctx,10.0,&ZeroSnap{ rising:false, channel:Some(5) },usize::MAX),None,"no such channel, no crossings");
+
+    ctx.channels.toggle(0);
+    assert_eq!(nearest_zero(&ctx,10.0,&any,usize::MAX),None,"with no active channels there's nothing to look at");
+  }
+}
@@ -16,7 +16,7 @@ use iced::{
 };
 
 use crate::{
-  snd::Snd,
+  snd::{Snd,Format},
   edit::Editor,
   lua,
   skin::Skin,
@@ -46,7 +46,7 @@ pub enum Msg {
 pub enum Action {
   Play(Arc<Snd>,f64,f64,f64,bool),
   Stop,
-  OpenNew(Arc<Snd>,Option<String>),
+  OpenNew(Arc<Snd>,Option<String>,Format),
   ConfigAudio,
   None
 }
@@ -59,8 +59,8 @@ pub struct Win {
 }
 
 impl Win {
-  pub fn new<S:Into<Arc<Snd>>>(snd:S,file:Option<String>) -> Result<Self> {
-    let editor = Rc::new(RefCell::new(Editor::new(snd.into(),file)));
+  pub fn new<S:Into<Arc<Snd>>>(snd:S,file:Option<String>,fmt:Format) -> Result<Self> {
+    let editor = Rc::new(RefCell::new(Editor::new(snd.into(),file,fmt)));
 
     Ok(Self {
       editor,
@@ -165,8 +165,8 @@ impl Win {
         }
       },
 
-      Some(lua::Action::NewWindow(s,f)) => {
-        Action::OpenNew(s,f)
+      Some(lua::Action::NewWindow(s,f,fmt)) => {
+        Action::OpenNew(s,f,fmt)
       },
 
       Some(lua::Action::ConfigAudio) => {
@@ -1,24 +1,84 @@
 use mlua::prelude::*;
-use crate::edit::amp;
+use crate::{edit::amp,dsp::Curve};
 
 pub fn gain(l:&Lua,amt:f32) -> LuaResult<()> {
   let ed_cell = &mut super::grab_editor(l)?;
   let mut ed = ed_cell.borrow_mut();
   let ctx = ed.ctx_mut();
 
+  let label = format!("gain {} on {}",amt,ctx.channel_label());
   let new_ctx = amp::gain(ctx,amt);
-  ed.push_new(new_ctx);
+  ed.push_new(new_ctx,label);
 
   Ok(())
 }
 
-pub fn fade(l:&Lua,(start,end):(f32,f32)) -> LuaResult<()> {
+fn parse_curve(curve:Option<String>) -> LuaResult<Curve> {
+  curve.map(|c|c.parse::<Curve>()).transpose().into_lua_err().map(|c|c.unwrap_or(Curve::Linear))
+}
+
+pub fn fade(l:&Lua,(start,end,curve):(f32,f32,Option<String>)) -> LuaResult<()> {
   let ed_cell = &mut super::grab_editor(l)?;
   let mut ed = ed_cell.borrow_mut();
   let ctx = ed.ctx_mut();
 
-  let new_ctx = amp::lin_fade(ctx,start,end);
-  ed.push_new(new_ctx);
+  let curve = parse_curve(curve)?;
+  let label = format!("{} fade {} to {} on {}",curve,start,end,ctx.channel_label());
+  let new_ctx = amp::fade(ctx,start,end,curve);
+  ed.push_new(new_ctx,label);
+
+  Ok(())
+}
+
+//these only work on a selection, fading the whole file by accident is no good
+pub fn fade_in(l:&Lua,curve:Option<String>) -> LuaResult<()> {
+  let ed_cell = &mut super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx();
+
+  if ctx.selected_region().is_none() {
+    return Ok(());
+  }
+
+  let curve = parse_curve(curve)?;
+  let label = format!("{} fade in on {}",curve,ctx.channel_label());
+  let new_ctx = amp::fade(ctx,0.0,1.0,curve);
+  ed.push_new(new_ctx,label);
+
+  Ok(())
+}
+
+pub fn fade_out(l:&Lua,curve:Option<String>) -> LuaResult<()> {
+  let ed_cell = &mut super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx();
+
+  if ctx.selected_region().is_none() {
+    return Ok(());
+  }
+
+  let curve = parse_curve(curve)?;
+  let label = format!("{} fade out on {}",curve,ctx.channel_label());
+  let new_ctx = amp::fade(ctx,1.0,0.0,curve);
+  ed.push_new(new_ctx,label);
+
+  Ok(())
+}
+
+//fades out of the selection and into start..finish, cutting out whatever's
+//between them. defaults to equal power
+pub fn crossfade_regions(l:&Lua,(start,finish,curve):(f64,f64,Option<String>)) -> LuaResult<()> {
+  let ed_cell = &mut super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx();
+
+  let curve = curve.map(|c|c.parse::<Curve>()).transpose().into_lua_err()?.unwrap_or_default();
+  let label = format!("{} crossfade on {}",curve,ctx.channel_label());
+  let b = (start.max(0.0) as usize,finish.max(0.0) as usize);
+
+  if let Some(new_ctx) = amp::crossfade(ctx,b,curve) {
+    ed.push_new(new_ctx,label);
+  }
 
   Ok(())
 }
@@ -28,8 +88,81 @@ pub fn normalize(l:&Lua,level:Option<f32>) -> LuaResult<()> {
   let mut ed = ed_cell.borrow_mut();
   let ctx = ed.ctx_mut();
 
+  let label = format!("normalize to {} on {}",level.unwrap_or(1.0),ctx.channel_label());
   let new_ctx = amp::normalize(ctx,level);
-  ed.push_new(new_ctx);
+  ed.push_new(new_ctx,label);
 
   Ok(())
 }
+
+//prints the loudness of the selection and hands it back as
+//{integrated=,range=,true_peak=,momentary_max=,short_term_max=}
+pub fn loudness(l:&Lua,_:()) -> LuaResult<LuaTable> {
+  let ed_cell = &mut super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let lu = amp::loudness(ed.ctx());
+
+  ed.print_nfo(format!(
+    "integrated {:.1} LUFS, range {:.1} LU, true peak {:.1} dBTP, max momentary {:.1}, max short term {:.1}",
+    lu.integrated,lu.range,lu.true_peak,lu.momentary_max,lu.short_term_max
+  ));
+
+  let out = l.create_table()?;
+  out.set("integrated",lu.integrated)?;
+  out.set("range",lu.range)?;
+  out.set("true_peak",lu.true_peak)?;
+  out.set("momentary_max",lu.momentary_max)?;
+  out.set("short_term_max",lu.short_term_max)?;
+  Ok(out)
+}
+
+//like normalize_loudness(-23,-1) for ebu r128 or normalize_loudness(-14,-1) for streaming
+pub fn normalize_loudness(l:&Lua,(target,ceiling):(f64,Option<f64>)) -> LuaResult<()> {
+  let ed_cell = &mut super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx();
+
+  let label = format!("normalize to {} LUFS on {}",target,ctx.channel_label());
+  match amp::normalize_loudness(ctx,target,ceiling) {
+    Some((new_ctx,db)) => {
+      ed.push_new(new_ctx,label);
+      ed.print_nfo(format!("gain {:.2} dB",db));
+    },
+    None => ed.print_err("can't normalize silence".to_string())
+  }
+
+  Ok(())
+}
+
+//prints a line per active channel for the selection and hands back a list of
+//{channel=,min=,max=,peak=,rms=,dc=,crest=,clipped=,zero_rate=}, levels are linear
+pub fn stats(l:&Lua,_:()) -> LuaResult<LuaTable> {
+  let ed_cell = &mut super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let st = crate::edit::stats::stats(ed.ctx());
+  let db = |v:f32|20.0*v.max(1e-10).log10();
+
+  ed.print_nfo("ch   peak dB   rms dB   dc      crest dB  clipped  zc/s".to_string());
+
+  let out = l.create_table()?;
+  for s in st {
+    ed.print_nfo(format!(
+      "{:<4} {:>7.2}  {:>7.2}  {:>+.4}  {:>7.2}  {:>7}  {:>7.1}",
+      s.channel,db(s.peak),db(s.rms),s.dc,db(s.crest),s.clipped,s.zero_rate
+    ));
+
+    let t = l.create_table()?;
+    t.set("channel",s.channel)?;
+    t.set("min",s.min)?;
+    t.set("max",s.max)?;
+    t.set("peak",s.peak)?;
+    t.set("rms",s.rms)?;
+    t.set("dc",s.dc)?;
+    t.set("crest",s.crest)?;
+    t.set("clipped",s.clipped)?;
+    t.set("zero_rate",s.zero_rate)?;
+    out.push(t)?;
+  }
+
+  Ok(out)
+}
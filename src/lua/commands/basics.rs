@@ -12,9 +12,10 @@ pub fn insert_silence(l:&Lua,len:usize) -> LuaResult<()> {
   });
 
   let new_snd = crate::snd::Snd::from_iter(ctx.snd.sample_rate(),new_sqs);
-  let out = ctx.flip(new_snd.into());
+  let mut out = ctx.flip(new_snd.into());
+  out.meta.insert(0,len);
 
-  ed.push_new(out);
+  ed.push_new(out,format!("insert {} samples of silence",len));
   Ok(())
 }
 
@@ -25,6 +26,70 @@ pub fn undo(l:&Lua,_:()) -> LuaResult<Option<Action>> {
   Ok(None)
 }
 
+pub fn redo(l:&Lua,_:()) -> LuaResult<bool> {
+  let ed = super::grab_editor(l)?;
+  let redone = ed.borrow_mut().redo();
+  ed.borrow_mut().dirty_up();
+  Ok(redone)
+}
+
+//every entry as {id=,label=,parent=,current=,bytes=}, parents are how the branches
+//hang together and bytes is the sample data only that entry is keeping around
+pub fn history(l:&Lua,_:()) -> LuaResult<LuaTable> {
+  let ed = super::grab_editor(l)?;
+  let out = l.create_table()?;
+
+  for e in ed.borrow().history() {
+    let t = l.create_table()?;
+    t.set("id",e.id)?;
+    t.set("label",e.label)?;
+    t.set("parent",e.parent)?;
+    t.set("current",e.current)?;
+    t.set("bytes",e.bytes)?;
+    out.push(t)?;
+  }
+
+  Ok(out)
+}
+
+pub fn print_history(l:&Lua,_:()) -> LuaResult<()> {
+  let ed_cell = super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+
+  for e in ed.history() {
+    let mark = if e.current { ">" } else { " " };
+    let mb = e.bytes as f64 / (1024.0 * 1024.0);
+    ed.print_nfo(format!("{}{} {} ({:.1} MB)",mark,e.id,e.label,mb));
+  }
+
+  Ok(())
+}
+
+//how many MB of samples the history can hold onto before the
+//oldest entries get dropped, hands back the budget either way
+pub fn undo_budget(l:&Lua,mb:Option<f64>) -> LuaResult<f64> {
+  let ed_cell = super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let mb_bytes = 1024.0 * 1024.0;
+
+  if let Some(mb) = mb {
+    ed.set_undo_budget((mb.max(0.0) * mb_bytes) as usize);
+  }
+
+  Ok(ed.undo_budget() as f64 / mb_bytes)
+}
+
+pub fn jump_history(l:&Lua,id:usize) -> LuaResult<()> {
+  let ed = super::grab_editor(l)?;
+
+  if !ed.borrow_mut().jump(id) {
+    return Err(format!("there's no history entry {}",id)).into_lua_err();
+  }
+
+  ed.borrow_mut().dirty_up();
+  Ok(())
+}
+
 pub fn activate_cmd_line(_l:&Lua,_:()) -> LuaResult<Action> {
   Ok(Action::ActivateCmdLine)
 }
@@ -81,9 +146,10 @@ pub fn reverse(l:&Lua,_:()) -> LuaResult<()> {
   let mut ed = ed_cell.borrow_mut();
   let ctx = ed.ctx();
 
+  let label = format!("reverse {}",ctx.channel_label());
   let revd = crate::edit::fx::reverse(ctx);
 
-  ed.push_new(revd);
+  ed.push_new(revd,label);
   Ok(())
 }
 
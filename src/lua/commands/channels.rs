@@ -7,7 +7,7 @@ pub fn solo(l:&Lua,chan:usize) -> LuaResult<()> {
   let ctx = ed.ctx_mut();
 
   let new_ctx = channels::solo(ctx,chan);
-  ed.push_new(new_ctx);
+  ed.push_new(new_ctx,format!("solo ch {}",chan));
 
   Ok(())
 }
@@ -18,7 +18,7 @@ pub fn delete(l:&Lua,chan:usize) -> LuaResult<()> {
   let ctx = ed.ctx_mut();
 
   let new_ctx = channels::delete(ctx,chan);
-  ed.push_new(new_ctx);
+  ed.push_new(new_ctx,format!("delete ch {}",chan));
 
   Ok(())
 
@@ -30,7 +30,7 @@ pub fn insert(l:&Lua,_:()) -> LuaResult<()> {
   let ctx = ed.ctx_mut();
 
   let new_ctx = channels::insert(ctx);
-  ed.push_new(new_ctx);
+  ed.push_new(new_ctx,"insert channel".into());
 
   Ok(())
 
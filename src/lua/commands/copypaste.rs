@@ -2,9 +2,46 @@ use mlua::prelude::*;
 use super::super::edit_userdata::LuaSnd;
 use crate::{
   edit::paste,
-  edit::delete
+  edit::delete,
+  edit::splice::Crossfade
 };
 
+//per call overrides for the splice crossfade, {fade=ms,curve=},
+//whatever's left out comes from the editor's setting
+fn parse_crossfade(xf:Crossfade,opts:Option<LuaTable>) -> LuaResult<Crossfade> {
+  let mut xf = xf;
+
+  let Some(opts) = opts else {
+    return Ok(xf)
+  };
+
+  if let Some(ms) = opts.get::<_,Option<f64>>("fade")? {
+    xf.ms = ms;
+  }
+
+  if let Some(curve) = opts.get::<_,Option<String>>("curve")? {
+    xf.curve = curve.parse().into_lua_err()?;
+  }
+
+  Ok(xf)
+}
+
+//sets the crossfade every delete, paste and crop uses, 0 turns it off
+pub fn crossfade(l:&Lua,(ms,curve):(Option<f64>,Option<String>)) -> LuaResult<f64> {
+  let ed_cell = super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+
+  if let Some(ms) = ms {
+    ed.crossfade_mut().ms = ms.max(0.0);
+  }
+
+  if let Some(c) = curve {
+    ed.crossfade_mut().curve = c.parse().into_lua_err()?;
+  }
+
+  Ok(ed.crossfade().ms)
+}
+
 pub fn get_snd(l:&Lua,_:()) -> LuaResult<LuaSnd> {
   let ed_cell = super::grab_editor(l)?;
   let ed = ed_cell.borrow();
@@ -15,14 +52,16 @@ pub fn get_snd(l:&Lua,_:()) -> LuaResult<LuaSnd> {
   Ok(snd.into())
 }
 
-pub fn paste(l:&Lua,snd:LuaSnd) -> LuaResult<()> {
+pub fn paste(l:&Lua,(snd,opts):(LuaSnd,Option<LuaTable>)) -> LuaResult<()> {
   let ed_cell = super::grab_editor(l)?;
   let mut ed = ed_cell.borrow_mut();
+  let xf = parse_crossfade(ed.crossfade(),opts)?;
   let ctx = ed.ctx();
   
-  let new_ctx = paste::insert_or_replace(ctx,snd.as_ref());
+  let label = format!("paste on {}",ctx.channel_label());
+  let new_ctx = paste::insert_or_replace(ctx,snd.as_ref(),&xf);
 
-  ed.push_new(new_ctx);
+  ed.push_new(new_ctx,label);
   ed.dirty_up();
 
   Ok(())
@@ -36,8 +75,9 @@ pub fn mix_in(l:&Lua,(src,src_gain,tgt_gain):(LuaSnd,Option<f32>,Option<f32>)) -
   let src_gain = src_gain.unwrap_or(1.0);
   let tgt_gain = tgt_gain.unwrap_or(1.0); 
   
+  let label = format!("mix in on {}",ctx.channel_label());
   let new_ctx = paste::mix_in(ctx,src.as_ref(),src_gain,tgt_gain);
-  ed.push_new(new_ctx);
+  ed.push_new(new_ctx,label);
 
   Ok(())
 }
@@ -45,26 +85,30 @@ pub fn mix_in(l:&Lua,(src,src_gain,tgt_gain):(LuaSnd,Option<f32>,Option<f32>)) -
 //=============================================================================
 //This stuff is here for now, it's not a good file to have them in
 //but I can't think of what I want to call the file to have them in
-pub fn delete(l:&Lua,_:()) -> LuaResult<()> {
+pub fn delete(l:&Lua,opts:Option<LuaTable>) -> LuaResult<()> {
   let ed_cell = super::grab_editor(l)?;
   let mut ed = ed_cell.borrow_mut();
+  let xf = parse_crossfade(ed.crossfade(),opts)?;
   let ctx = ed.ctx();
 
-  if let Some(new_guy) = delete::remove_selected(ctx) {
-    ed.push_new(new_guy);
+  let label = format!("delete on {}",ctx.channel_label());
+  if let Some(new_guy) = delete::remove_selected(ctx,&xf) {
+    ed.push_new(new_guy,label);
     ed.dirty_up();
   }
 
   Ok(())
 }
 
-pub fn crop(l:&Lua,_:()) -> LuaResult<()> {
+pub fn crop(l:&Lua,opts:Option<LuaTable>) -> LuaResult<()> {
   let ed_cell = super::grab_editor(l)?;
   let mut ed = ed_cell.borrow_mut();
+  let xf = parse_crossfade(ed.crossfade(),opts)?;
   let ctx = ed.ctx();
 
-  if let Some(new_guy) = delete::remove_non_selected(ctx) {
-    ed.push_new(new_guy);
+  let label = format!("crop on {}",ctx.channel_label());
+  if let Some(new_guy) = delete::remove_non_selected(ctx,&xf) {
+    ed.push_new(new_guy,label);
     ed.dirty_up();
   }
 
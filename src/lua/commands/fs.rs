@@ -1,12 +1,19 @@
 use mlua::prelude::*;
 use crate::lua::Action;
+use crate::snd::Format;
+
+//the format arg is optional, if it's missing the editor's
+//current format is used
+fn parse_format(fmt:Option<String>) -> LuaResult<Option<Format>> {
+  fmt.map(|f|f.parse::<Format>()).transpose().into_lua_err()
+}
 
 pub fn load(l:&Lua,p:Option<String>) -> LuaResult<()> {
   let p : std::path::PathBuf = match p {
     Some(words) => words.into(),
     None => {
       if let Some(pb) = rfd::FileDialog::new()
-      .add_filter("sound",&["wav"])
+      .add_filter("sound",&crate::snd::load_extensions())
       .set_directory(".")
       .pick_file()
       {
@@ -23,10 +30,12 @@ pub fn load(l:&Lua,p:Option<String>) -> LuaResult<()> {
 
   let path_str = p.to_str().ok_or("couldn't convert file path to string").into_lua_err()?;
   let path_strn = path_str.to_string();
-  let snd = crate::snd::load_wav(p).into_lua_err()?;
+  let label = format!("load {}",path_str);
+  let (snd,fmt) = crate::snd::load(p).into_lua_err()?;
   let snd : std::sync::Arc<crate::snd::Snd> = snd.into();
   ed.set_path(Some(path_strn));
-  ed.reset_stack(snd.into());
+  ed.save_opts_mut().format = fmt;
+  ed.reset_stack(snd.into(),label);
 
   Ok(())
 }
@@ -36,7 +45,7 @@ pub fn load_new(_l:&Lua,p:Option<String>) -> LuaResult<Option<Action>> {
     Some(words) => words.into(),
     None => {
       if let Some(pb) = rfd::FileDialog::new()
-      .add_filter("sound",&["wav"])
+      .add_filter("sound",&crate::snd::load_extensions())
       .set_directory(".")
       .pick_file()
       {
@@ -49,69 +58,196 @@ pub fn load_new(_l:&Lua,p:Option<String>) -> LuaResult<Option<Action>> {
   };
 
   let path_str = p.to_str().expect("wow").to_string();
-  let snd = crate::snd::load_wav(p).into_lua_err()?;
-  Ok(Some( Action::NewWindow(snd.into(),Some(path_str))) )
+  let (snd,fmt) = crate::snd::load(p).into_lua_err()?;
+  Ok(Some( Action::NewWindow(snd.into(),Some(path_str),fmt)) )
+}
+
+//reads {rate=,channels=,format=,endian=,offset=} out of a table,
+//anything left out falls back to 44.1k mono little endian in the given format
+fn parse_layout(opts:Option<LuaTable>,fmt:Format) -> LuaResult<crate::snd::RawLayout> {
+  let mut layout = crate::snd::RawLayout{ format:fmt, ..Default::default() };
+
+  let Some(opts) = opts else {
+    return Ok(layout)
+  };
+
+  if let Some(rate) = opts.get::<_,Option<usize>>("rate")? {
+    layout.sample_rate = rate;
+  }
+
+  if let Some(chans) = opts.get::<_,Option<usize>>("channels")? {
+    layout.channels = chans;
+  }
+
+  if let Some(fmt) = parse_format(opts.get("format")?)? {
+    layout.format = fmt;
+  }
+
+  if let Some(endian) = opts.get::<_,Option<String>>("endian")? {
+    layout.big_endian = match endian.as_str() {
+      "little" | "le" => false,
+      "big" | "be" => true,
+      _ => Err(format!("unknown endianness {}, try little or big",endian)).into_lua_err()?
+    };
+  }
+
+  if let Some(offset) = opts.get::<_,Option<u64>>("offset")? {
+    layout.offset = offset;
+  }
+
+  Ok(layout)
+}
+
+//there's no header to sniff so the path isn't kept around,
+//otherwise a plain save would write a wav over the raw file
+pub fn load_raw(l:&Lua,(p,opts):(Option<String>,Option<LuaTable>)) -> LuaResult<()> {
+  let p : std::path::PathBuf = match p {
+    Some(words) => words.into(),
+    None => {
+      if let Some(pb) = rfd::FileDialog::new()
+      .set_directory(".")
+      .pick_file()
+      {
+        pb
+      }
+      else {
+        return Ok(())
+      }
+    }
+  };
+
+  let layout = parse_layout(opts,Format::Int16)?;
+  let snd = crate::snd::load_raw(p,&layout).into_lua_err()?;
+  let snd : std::sync::Arc<crate::snd::Snd> = snd.into();
+
+  let ed_cell = super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  ed.set_path(None);
+  ed.save_opts_mut().format = layout.format;
+  ed.reset_stack(snd.into(),"load raw".into());
+
+  Ok(())
 }
 
-pub fn save(l:&Lua,p:Option<String>) -> LuaResult<()> {
+//an export, so it doesn't touch the editor's path or format
+pub fn save_raw(l:&Lua,(p,opts):(Option<String>,Option<LuaTable>)) -> LuaResult<()> {
+  let ed_cell = super::grab_editor(l)?;
+  let ed = ed_cell.borrow();
+
+  let layout = parse_layout(opts,ed.save_opts().format)?;
+
+  let p : std::path::PathBuf = match p {
+    Some(words) => words.into(),
+    None => {
+      if let Some(pb) = rfd::FileDialog::new()
+      .set_directory(".")
+      .save_file()
+      {
+        pb
+      }
+      else {
+        return Ok(())
+      }
+    }
+  };
+
+  let ctx = ed.ctx();
+  crate::snd::save_raw(&ctx.snd,p,&layout,ed.save_opts().dither).into_lua_err()
+}
+
+//sets the dither used when saving to 16 or 24 bits,
+//with no args it just tells you what it is
+pub fn dither(l:&Lua,mode:Option<String>) -> LuaResult<String> {
+  let ed_cell = super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+
+  if let Some(m) = mode {
+    let d = m.parse::<crate::dsp::Dither>().into_lua_err()?;
+    ed.save_opts_mut().dither = d;
+  }
+
+  Ok(ed.save_opts().dither.to_string())
+}
+
+//0 is fastest, 8 is smallest
+pub fn flac_level(l:&Lua,level:Option<u8>) -> LuaResult<u8> {
   let ed_cell = super::grab_editor(l)?;
   let mut ed = ed_cell.borrow_mut();
+
+  if let Some(lvl) = level {
+    ed.save_opts_mut().flac_level = lvl.min(8);
+  }
+
+  Ok(ed.save_opts().flac_level)
+}
+
+pub fn save(l:&Lua,(p,fmt):(Option<String>,Option<String>)) -> LuaResult<()> {
+  let ed_cell = super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let mut opts = ed.save_opts();
+  opts.format = parse_format(fmt)?.unwrap_or(opts.format);
  
   //ok they gave us a path
   if let Some(ref words) = p {
     let ctx = ed.ctx();
-    crate::snd::save_wav(&ctx.snd,words).into_lua_err()?;
+    crate::snd::save(&ctx.snd,&ctx.meta,words,&opts).into_lua_err()?;
     ed.set_path(p);
+    ed.save_opts_mut().format = opts.format;
     return Ok(())
   }
 
   if let Some(words) = ed.path() {
     let ctx = ed.ctx();
-    crate::snd::save_wav(&ctx.snd,words).into_lua_err()?;
+    crate::snd::save(&ctx.snd,&ctx.meta,words,&opts).into_lua_err()?;
+    ed.save_opts_mut().format = opts.format;
     return Ok(())
   }
 
   let dialog_path =  rfd::FileDialog::new()
-  .add_filter("sound",&["wav"])
+  .add_filter("sound",&crate::snd::EXTENSIONS)
   .set_directory(".")
   .save_file();
 
   if let Some(pb) = dialog_path{
     let ctx = ed.ctx();
     let path_str = pb.to_str().expect("wow").to_string();
-    crate::snd::save_wav(&ctx.snd,pb).into_lua_err()?;
+    crate::snd::save(&ctx.snd,&ctx.meta,pb,&opts).into_lua_err()?;
     ed.set_path(path_str);
+    ed.save_opts_mut().format = opts.format;
     return Ok(())
   }
 
   Ok(())
 }
 
-pub fn save_as(l:&Lua,p:Option<String>) -> LuaResult<()> {
+pub fn save_as(l:&Lua,(p,fmt):(Option<String>,Option<String>)) -> LuaResult<()> {
   let ed_cell = super::grab_editor(l)?;
   let mut ed = ed_cell.borrow_mut();
+  let mut opts = ed.save_opts();
+  opts.format = parse_format(fmt)?.unwrap_or(opts.format);
  
   //ok they gave us a path
   if let Some(ref words) = p {
     let ctx = ed.ctx();
-    crate::snd::save_wav(&ctx.snd,words).into_lua_err()?;
+    crate::snd::save(&ctx.snd,&ctx.meta,words,&opts).into_lua_err()?;
     ed.set_path(p);
+    ed.save_opts_mut().format = opts.format;
     return Ok(())
   }
 
   let dialog_path =  rfd::FileDialog::new()
-  .add_filter("sound",&["wav"])
+  .add_filter("sound",&crate::snd::EXTENSIONS)
   .set_directory(".")
   .save_file();
 
   if let Some(pb) = dialog_path{
     let ctx = ed.ctx();
     let path_str = pb.to_str().expect("wow").to_string();
-    crate::snd::save_wav(&ctx.snd,pb).into_lua_err()?;
+    crate::snd::save(&ctx.snd,&ctx.meta,pb,&opts).into_lua_err()?;
     ed.set_path(path_str);
+    ed.save_opts_mut().format = opts.format;
     return Ok(())
   }
 
   Ok(())
 }
-
@@ -0,0 +1,213 @@
+use mlua::prelude::*;
+use super::super::edit_userdata::LuaSnd;
+use crate::{
+  edit::fx,
+  dsp::{Filter,DEFAULT_Q,Dynamics,DynamicsMode,Delay,Reverb}
+};
+
+fn run_filter(l:&Lua,kind:Filter,freq:f64,q:Option<f64>) -> LuaResult<()> {
+  let ed_cell = &mut super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx();
+
+  let label = format!("{} at {}Hz on {}",kind,freq,ctx.channel_label());
+  let new_ctx = fx::filter(ctx,kind,freq,q.unwrap_or(DEFAULT_Q));
+  ed.push_new(new_ctx,label);
+
+  Ok(())
+}
+
+pub fn lowpass(l:&Lua,(freq,q):(f64,Option<f64>)) -> LuaResult<()> {
+  run_filter(l,Filter::Lowpass,freq,q)
+}
+
+pub fn highpass(l:&Lua,(freq,q):(f64,Option<f64>)) -> LuaResult<()> {
+  run_filter(l,Filter::Highpass,freq,q)
+}
+
+pub fn bandpass(l:&Lua,(freq,q):(f64,Option<f64>)) -> LuaResult<()> {
+  run_filter(l,Filter::Bandpass,freq,q)
+}
+
+pub fn notch(l:&Lua,(freq,q):(f64,Option<f64>)) -> LuaResult<()> {
+  run_filter(l,Filter::Notch,freq,q)
+}
+
+pub fn allpass(l:&Lua,(freq,q):(f64,Option<f64>)) -> LuaResult<()> {
+  run_filter(l,Filter::Allpass,freq,q)
+}
+
+//gains are in dB
+pub fn eq(l:&Lua,(freq,gain,q):(f64,f64,Option<f64>)) -> LuaResult<()> {
+  run_filter(l,Filter::Peak(gain),freq,q)
+}
+
+pub fn low_shelf(l:&Lua,(freq,gain,q):(f64,f64,Option<f64>)) -> LuaResult<()> {
+  run_filter(l,Filter::LowShelf(gain),freq,q)
+}
+
+pub fn high_shelf(l:&Lua,(freq,gain,q):(f64,f64,Option<f64>)) -> LuaResult<()> {
+  run_filter(l,Filter::HighShelf(gain),freq,q)
+}
+
+//{threshold=,ratio=,knee=,attack=,release=,lookahead=,range=,makeup=,link=}
+//in dB and ms, anything left out gets the mode's default. link is on by default
+fn run_dynamics(l:&Lua,mode:DynamicsMode,opts:Option<LuaTable>) -> LuaResult<()> {
+  let mut dy = Dynamics::new(mode);
+  let mut link = true;
+
+  if let Some(opts) = opts {
+    let fields : [(&str,&mut f32);8] = [
+      ("threshold",&mut dy.threshold),
+      ("ratio",&mut dy.ratio),
+      ("knee",&mut dy.knee),
+      ("attack",&mut dy.attack),
+      ("release",&mut dy.release),
+      ("lookahead",&mut dy.lookahead),
+      ("range",&mut dy.range),
+      ("makeup",&mut dy.makeup)
+    ];
+
+    for (name,field) in fields {
+      if let Some(v) = opts.get::<_,Option<f32>>(name)? {
+        *field = v;
+      }
+    }
+
+    link = opts.get::<_,Option<bool>>("link")?.unwrap_or(true);
+  }
+
+  let ed_cell = &mut super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx();
+
+  let label = format!("{} at {}dB on {}",mode,dy.threshold,ctx.channel_label());
+  let new_ctx = fx::dynamics(ctx,&dy,link);
+  ed.push_new(new_ctx,label);
+
+  Ok(())
+}
+
+pub fn compress(l:&Lua,opts:Option<LuaTable>) -> LuaResult<()> {
+  run_dynamics(l,DynamicsMode::Compressor,opts)
+}
+
+pub fn limit(l:&Lua,opts:Option<LuaTable>) -> LuaResult<()> {
+  run_dynamics(l,DynamicsMode::Limiter,opts)
+}
+
+pub fn expand(l:&Lua,opts:Option<LuaTable>) -> LuaResult<()> {
+  run_dynamics(l,DynamicsMode::Expander,opts)
+}
+
+pub fn gate(l:&Lua,opts:Option<LuaTable>) -> LuaResult<()> {
+  run_dynamics(l,DynamicsMode::Gate,opts)
+}
+
+//with no frequency the mean gets taken out, with one it's a highpass
+//at that frequency for offsets that drift
+pub fn remove_dc(l:&Lua,freq:Option<f64>) -> LuaResult<()> {
+  let ed_cell = &mut super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx();
+
+  let label = format!("remove dc on {}",ctx.channel_label());
+  let new_ctx = match freq {
+    Some(f) => fx::remove_dc_highpass(ctx,f),
+    None => fx::remove_dc(ctx)
+  };
+
+  ed.push_new(new_ctx,label);
+  Ok(())
+}
+
+pub fn invert(l:&Lua,_:()) -> LuaResult<()> {
+  let ed_cell = &mut super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx();
+
+  let label = format!("invert {}",ctx.channel_label());
+  let new_ctx = fx::invert(ctx);
+  ed.push_new(new_ctx,label);
+  Ok(())
+}
+
+//wet and dry are gains, the default is all wet. tail lets the ir
+//ring out past the end of the selection
+pub fn convolve(l:&Lua,(ir,wet,dry,tail):(LuaSnd,Option<f32>,Option<f32>,Option<bool>)) -> LuaResult<()> {
+  let ed_cell = &mut super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx();
+
+  let label = format!("convolve on {}",ctx.channel_label());
+  let new_ctx = fx::convolve(ctx,ir.as_ref(),wet.unwrap_or(1.0),dry.unwrap_or(0.0),tail.unwrap_or(false));
+  ed.push_new(new_ctx,label);
+
+  Ok(())
+}
+
+//{ms=,divs=,feedback=,lowpass=,highpass=,ping_pong=,mix=,tail=}
+//the time is either ms or ruler divisions, ms wins if both are there
+pub fn delay(l:&Lua,opts:Option<LuaTable>) -> LuaResult<()> {
+  let ed_cell = &mut super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx();
+
+  let opts = match opts {
+    Some(o) => o,
+    None => l.create_table()?
+  };
+
+  let sr = ctx.snd.sample_rate() as f64;
+  let time = match (opts.get::<_,Option<f64>>("ms")?,opts.get::<_,Option<f64>>("divs")?) {
+    (Some(ms),_) => ms*sr/1000.0,
+    (None,Some(divs)) => match ctx.ruler {
+      Some(r) => divs*r.scale(),
+      None => return Err("there's no ruler to count divisions on".to_string()).into_lua_err()
+    },
+    (None,None) => 250.0*sr/1000.0
+  };
+
+  let d = Delay::new(time);
+  let d = Delay {
+    feedback:opts.get::<_,Option<f32>>("feedback")?.unwrap_or(d.feedback),
+    lowpass:opts.get("lowpass")?,
+    highpass:opts.get("highpass")?,
+    ping_pong:opts.get::<_,Option<bool>>("ping_pong")?.unwrap_or(d.ping_pong),
+    mix:opts.get::<_,Option<f32>>("mix")?.unwrap_or(d.mix),
+    ..d
+  };
+
+  let tail = opts.get::<_,Option<bool>>("tail")?.unwrap_or(false);
+  let label = format!("delay {:.0}ms on {}",time*1000.0/sr,ctx.channel_label());
+  let new_ctx = fx::delay(ctx,&d,tail);
+  ed.push_new(new_ctx,label);
+
+  Ok(())
+}
+
+//{room=,damping=,mix=,tail=}, all but tail go from 0 to 1
+pub fn reverb(l:&Lua,opts:Option<LuaTable>) -> LuaResult<()> {
+  let ed_cell = &mut super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx();
+
+  let opts = match opts {
+    Some(o) => o,
+    None => l.create_table()?
+  };
+
+  let d = Reverb::default();
+  let rv = Reverb {
+    room:opts.get::<_,Option<f32>>("room")?.unwrap_or(d.room),
+    damping:opts.get::<_,Option<f32>>("damping")?.unwrap_or(d.damping),
+    mix:opts.get::<_,Option<f32>>("mix")?.unwrap_or(d.mix)
+  };
+
+  let tail = opts.get::<_,Option<bool>>("tail")?.unwrap_or(false);
+  let label = format!("reverb on {}",ctx.channel_label());
+  let new_ctx = fx::reverb(ctx,&rv,tail);
+  ed.push_new(new_ctx,label);
+
+  Ok(())
+}
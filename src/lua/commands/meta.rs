@@ -0,0 +1,141 @@
+use mlua::prelude::*;
+use crate::snd::{Cue,Loop,Sampler,Bext};
+
+use super::grab_editor;
+
+//all the cues as a list of {id=,pos=,label=}
+pub fn cues(l:&Lua,_:()) -> LuaResult<LuaTable> {
+  let ed_cell = grab_editor(l)?;
+  let ed = ed_cell.borrow();
+  let out = l.create_table()?;
+
+  for c in ed.ctx().meta.cues.iter() {
+    let t = l.create_table()?;
+    t.set("id",c.id)?;
+    t.set("pos",c.pos)?;
+    t.set("label",c.label.clone())?;
+    out.push(t)?;
+  }
+
+  Ok(out)
+}
+
+//drops a cue at the cursor, hands back the id
+pub fn add_cue(l:&Lua,label:Option<String>) -> LuaResult<Option<u32>> {
+  let ed_cell = grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+
+  let mut new_ctx = ed.ctx().clone();
+  let Some(pos) = new_ctx.cursor else {
+    return Ok(None)
+  };
+
+  let id = new_ctx.meta.next_cue_id();
+  new_ctx.meta.cues.push(Cue{ id, pos:pos.floor() as usize, label });
+  new_ctx.meta.cues.sort_by_key(|c|c.pos);
+  ed.push_new(new_ctx,format!("add cue {}",id));
+
+  Ok(Some(id))
+}
+
+pub fn delete_cue(l:&Lua,id:u32) -> LuaResult<()> {
+  let ed_cell = grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+
+  let mut new_ctx = ed.ctx().clone();
+  new_ctx.meta.cues.retain(|c|c.id != id);
+  ed.push_new(new_ctx,format!("delete cue {}",id));
+
+  Ok(())
+}
+
+//loops as a list of {start=,finish=,count=}, finish is the last sample in the loop
+pub fn loops(l:&Lua,_:()) -> LuaResult<LuaTable> {
+  let ed_cell = grab_editor(l)?;
+  let ed = ed_cell.borrow();
+  let out = l.create_table()?;
+
+  for lp in ed.ctx().meta.sampler.iter().flat_map(|s|s.loops.iter()) {
+    let t = l.create_table()?;
+    t.set("start",lp.start)?;
+    t.set("finish",lp.end)?;
+    t.set("count",lp.count)?;
+    out.push(t)?;
+  }
+
+  Ok(out)
+}
+
+//turns the selection into the sampler loop, count 0 loops forever
+pub fn set_loop(l:&Lua,count:Option<u32>) -> LuaResult<()> {
+  let ed_cell = grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+
+  let mut new_ctx = ed.ctx().clone();
+  let Some(r) = new_ctx.selected_region() else {
+    return Ok(())
+  };
+
+  let (s,e) = r.sample_range();
+  if e <= s {
+    return Ok(())
+  }
+
+  //samplers want the sample period in nanoseconds
+  let period = (1e9 / new_ctx.snd.sample_rate() as f64) as u32;
+  let smpl = new_ctx.meta.sampler.get_or_insert(Sampler{ period, unity_note:60, ..Default::default() });
+  smpl.loops = vec![Loop{ cue_id:0, kind:0, start:s, end:e - 1, fraction:0, count:count.unwrap_or(0) }];
+  ed.push_new(new_ctx,"set loop".into());
+
+  Ok(())
+}
+
+pub fn clear_loops(l:&Lua,_:()) -> LuaResult<()> {
+  let ed_cell = grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+
+  let mut new_ctx = ed.ctx().clone();
+  if let Some(smpl) = &mut new_ctx.meta.sampler {
+    smpl.loops.clear();
+  }
+  ed.push_new(new_ctx,"clear loops".into());
+
+  Ok(())
+}
+
+//LIST/INFO tags, like tag("INAM") or tag("ICMT","a comment"),
+//setting one to "" gets rid of it
+pub fn tag(l:&Lua,(key,val):(String,Option<String>)) -> LuaResult<Option<String>> {
+  let ed_cell = grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+
+  if let Some(v) = val {
+    let mut new_ctx = ed.ctx().clone();
+    new_ctx.meta.set_info(&key,&v).into_lua_err()?;
+    ed.push_new(new_ctx,format!("tag {}",key));
+  }
+
+  Ok(ed.ctx().meta.info(&key).map(|v|v.to_string()))
+}
+
+//the broadcast wave description, setting it makes a bext chunk if there isn't one
+pub fn description(l:&Lua,text:Option<String>) -> LuaResult<Option<String>> {
+  let ed_cell = grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+
+  if let Some(t) = text {
+    let mut new_ctx = ed.ctx().clone();
+    new_ctx.meta.bext.get_or_insert_with(Bext::default).set_description(&t);
+    ed.push_new(new_ctx,"description".into());
+  }
+
+  Ok(ed.ctx().meta.bext.as_ref().map(|b|b.description()))
+}
+
+//whatever made the file, from the bext chunk
+pub fn originator(l:&Lua,_:()) -> LuaResult<Option<String>> {
+  let ed_cell = grab_editor(l)?;
+  let ed = ed_cell.borrow();
+
+  Ok(ed.ctx().meta.bext.as_ref().map(|b|b.originator()))
+}
@@ -15,6 +15,9 @@ mod channels;
 mod sample_rates;
 mod fs;
 mod time;
+mod meta;
+mod fx;
+mod silence;
 
 //Ok this function is gonna get real big, but I think it's nice to have it as
 //a reference for all the function names rather than splitting them out into
@@ -27,10 +30,19 @@ pub fn add_command_fns(l:&Lua,globals:&LuaTable) -> LuaResult<()> {
   globals.set("load_new",l.create_function(fs::load_new)?)?;
   globals.set("save",l.create_function(fs::save)?)?;
   globals.set("save_as",l.create_function(fs::save_as)?)?;
+  globals.set("load_raw",l.create_function(fs::load_raw)?)?;
+  globals.set("save_raw",l.create_function(fs::save_raw)?)?;
+  globals.set("dither",l.create_function(fs::dither)?)?;
+  globals.set("flac_level",l.create_function(fs::flac_level)?)?;
 
   //basics
   globals.set("insert_silence",l.create_function(basics::insert_silence)?)?;
   globals.set("undo",l.create_function(basics::undo)?)?;
+  globals.set("redo",l.create_function(basics::redo)?)?;
+  globals.set("history",l.create_function(basics::history)?)?;
+  globals.set("jump_history",l.create_function(basics::jump_history)?)?;
+  globals.set("print_history",l.create_function(basics::print_history)?)?;
+  globals.set("undo_budget",l.create_function(basics::undo_budget)?)?;
   globals.set("toggle_loop",l.create_function(basics::toggle_loop)?)?;
   globals.set("play",l.create_function(basics::play)?)?;
   globals.set("toggle_channel",l.create_function(basics::toggle_channel)?)?;
@@ -54,6 +66,13 @@ pub fn add_command_fns(l:&Lua,globals:&LuaTable) -> LuaResult<()> {
   globals.set("scale_ruler",l.create_function(ruler::rescale)?)?;
   globals.set("rule_time",l.create_function(ruler::time)?)?;
 
+  //zero crossings
+  globals.set("next_zero",l.create_function(ruler::next_zero)?)?;
+  globals.set("previous_zero",l.create_function(ruler::previous_zero)?)?;
+  globals.set("nearest_zero",l.create_function(ruler::nearest_zero)?)?;
+  globals.set("zero_snap",l.create_function(ruler::zero_snap)?)?;
+  globals.set("snap_to_zero",l.create_function(ruler::snap_to_zero)?)?;
+
   //nav
   globals.set("step_cursor",l.create_function(nav::step)?)?;
   globals.set("feather_selection",l.create_function(nav::feather)?)?;
@@ -81,16 +100,32 @@ pub fn add_command_fns(l:&Lua,globals:&LuaTable) -> LuaResult<()> {
   //delete
   globals.set("delete",l.create_function(copypaste::delete)?)?;
   globals.set("crop",l.create_function(copypaste::crop)?)?;
+  globals.set("crossfade",l.create_function(copypaste::crossfade)?)?;
+
 
+  //silence
+  globals.set("silences",l.create_function(silence::silences)?)?;
+  globals.set("sounds",l.create_function(silence::sounds)?)?;
+  globals.set("trim_silence",l.create_function(silence::trim_silence)?)?;
+  globals.set("shorten_silence",l.create_function(silence::shorten_silence)?)?;
 
   //amp
   globals.set("gain",l.create_function(amp::gain)?)?;
   globals.set("fade",l.create_function(amp::fade)?)?;
+  globals.set("fade_in",l.create_function(amp::fade_in)?)?;
+  globals.set("fade_out",l.create_function(amp::fade_out)?)?;
+  globals.set("crossfade_regions",l.create_function(amp::crossfade_regions)?)?;
   globals.set("normalize",l.create_function(amp::normalize)?)?;
+  globals.set("loudness",l.create_function(amp::loudness)?)?;
+  globals.set("normalize_loudness",l.create_function(amp::normalize_loudness)?)?;
+  globals.set("stats",l.create_function(amp::stats)?)?;
 
   //pitch and resample
   globals.set("resample",l.create_function(sample_rates::resample)?)?;
   globals.set("pitch_shift",l.create_function(sample_rates::pitch)?)?;
+  globals.set("stretch",l.create_function(sample_rates::stretch)?)?;
+  globals.set("stretch_to",l.create_function(sample_rates::stretch_to)?)?;
+  globals.set("transpose",l.create_function(sample_rates::transpose)?)?;
 
   //time helpers
   globals.set("seconds",l.create_function(time::seconds)?)?;
@@ -98,6 +133,38 @@ pub fn add_command_fns(l:&Lua,globals:&LuaTable) -> LuaResult<()> {
 
   //fx
   globals.set("reverse",l.create_function(basics::reverse)?)?;
+  globals.set("remove_dc",l.create_function(fx::remove_dc)?)?;
+  globals.set("invert",l.create_function(fx::invert)?)?;
+  globals.set("convolve",l.create_function(fx::convolve)?)?;
+  globals.set("delay",l.create_function(fx::delay)?)?;
+  globals.set("reverb",l.create_function(fx::reverb)?)?;
+
+  //filters
+  globals.set("lowpass",l.create_function(fx::lowpass)?)?;
+  globals.set("highpass",l.create_function(fx::highpass)?)?;
+  globals.set("bandpass",l.create_function(fx::bandpass)?)?;
+  globals.set("notch",l.create_function(fx::notch)?)?;
+  globals.set("allpass",l.create_function(fx::allpass)?)?;
+  globals.set("eq",l.create_function(fx::eq)?)?;
+  globals.set("low_shelf",l.create_function(fx::low_shelf)?)?;
+  globals.set("high_shelf",l.create_function(fx::high_shelf)?)?;
+
+  //dynamics
+  globals.set("compress",l.create_function(fx::compress)?)?;
+  globals.set("limit",l.create_function(fx::limit)?)?;
+  globals.set("expand",l.create_function(fx::expand)?)?;
+  globals.set("gate",l.create_function(fx::gate)?)?;
+
+  //wav metadata
+  globals.set("cues",l.create_function(meta::cues)?)?;
+  globals.set("add_cue",l.create_function(meta::add_cue)?)?;
+  globals.set("delete_cue",l.create_function(meta::delete_cue)?)?;
+  globals.set("loops",l.create_function(meta::loops)?)?;
+  globals.set("set_loop",l.create_function(meta::set_loop)?)?;
+  globals.set("clear_loops",l.create_function(meta::clear_loops)?)?;
+  globals.set("tag",l.create_function(meta::tag)?)?;
+  globals.set("description",l.create_function(meta::description)?)?;
+  globals.set("originator",l.create_function(meta::originator)?)?;
 
   Ok(())
 }
@@ -1,5 +1,6 @@
 use mlua::prelude::*;
 use crate::util::Ruler;
+use crate::edit::zeros::{self,ZeroSnap};
 
 use super::grab_editor;
 
@@ -110,3 +111,95 @@ pub fn time(l:&Lua,raw:f64) -> LuaResult<f64> {
     Ok(pos)
   }
 }
+
+//{rising=true,channel=n} picks which crossings count, otherwise it's
+//whatever zero snapping is set to, or any crossing in the active channels
+fn parse_zero_snap(ctx:&crate::edit::Ctx,opts:Option<LuaTable>) -> LuaResult<ZeroSnap> {
+  let mut zs = ctx.zero_snap.unwrap_or_default();
+
+  let Some(opts) = opts else {
+    return Ok(zs)
+  };
+
+  if let Some(rising) = opts.get::<_,Option<bool>>("rising")? {
+    zs.rising = rising;
+  }
+
+  if let Some(chan) = opts.get::<_,Option<usize>>("channel")? {
+    if chan >= ctx.snd.channels() {
+      return Err(format!("there's no channel {}, this has {}",chan,ctx.snd.channels())).into_lua_err();
+    }
+
+    zs.channel = Some(chan);
+  }
+
+  Ok(zs)
+}
+
+//like the marks these fall back to the ends of the sound
+pub fn next_zero(l:&Lua,(pos,opts):(f64,Option<LuaTable>)) -> LuaResult<f64> {
+  let ed_cell = &mut grab_editor(l)?;
+  let ed = ed_cell.borrow();
+  let ctx = ed.ctx();
+
+  let zs = parse_zero_snap(ctx,opts)?;
+  Ok(zeros::next_zero(ctx,pos,&zs,usize::MAX).map(|z|z as f64).unwrap_or(ctx.len()))
+}
+
+pub fn previous_zero(l:&Lua,(pos,opts):(f64,Option<LuaTable>)) -> LuaResult<f64> {
+  let ed_cell = &mut grab_editor(l)?;
+  let ed = ed_cell.borrow();
+  let ctx = ed.ctx();
+
+  let zs = parse_zero_snap(ctx,opts)?;
+  Ok(zeros::previous_zero(ctx,pos,&zs,usize::MAX).map(|z|z as f64).unwrap_or(0.0))
+}
+
+pub fn nearest_zero(l:&Lua,(pos,opts):(f64,Option<LuaTable>)) -> LuaResult<f64> {
+  let ed_cell = &mut grab_editor(l)?;
+  let ed = ed_cell.borrow();
+  let ctx = ed.ctx();
+
+  let zs = parse_zero_snap(ctx,opts)?;
+  Ok(zeros::nearest_zero(ctx,pos,&zs,usize::MAX).map(|z|z as f64).unwrap_or(pos))
+}
+
+//turns snapping clicks and drags to zero crossings on or off, hands back if it's on
+pub fn zero_snap(l:&Lua,(on,opts):(Option<bool>,Option<LuaTable>)) -> LuaResult<bool> {
+  let ed_cell = &mut grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx_mut();
+
+  let on = on.unwrap_or(ctx.zero_snap.is_none());
+  ctx.zero_snap = if on {
+    Some(parse_zero_snap(ctx,opts)?)
+  }
+  else {
+    None
+  };
+
+  Ok(on)
+}
+
+//moves the cursor and both ends of the selection onto crossings
+pub fn snap_to_zero(l:&Lua,opts:Option<LuaTable>) -> LuaResult<()> {
+  let ed_cell = &mut grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx_mut();
+
+  let zs = parse_zero_snap(ctx,opts)?;
+  let snap = |pt:f64|zeros::nearest_zero(ctx,pt,&zs,usize::MAX).map(|z|z as f64).unwrap_or(pt);
+
+  let (cursor,selection) = match (ctx.cursor,ctx.selection) {
+    (Some(pt),Some(len)) => {
+      let (c,e) = (snap(pt),snap(pt+len));
+      (Some(c),if c == e { None } else { Some(e - c) })
+    },
+    (Some(pt),None) => (Some(snap(pt)),None),
+    (c,s) => (c,s)
+  };
+
+  ctx.cursor = cursor;
+  ctx.selection = selection;
+  Ok(())
+}
@@ -1,14 +1,32 @@
 use mlua::prelude::*;
 use crate::edit::sample_rates;
+use crate::dsp::Quality;
 
-pub fn resample(l:&Lua,(rate,quality):(f64,Option<usize>)) -> LuaResult<()> {
+//quality is either a level or a table of polyphase settings,
+//{taps=,passband=,stopband=}, anything left out stays at the default
+pub fn resample(l:&Lua,(rate,quality):(f64,Option<LuaValue>)) -> LuaResult<()> {
   let ed_cell = &mut super::grab_editor(l)?;
   let mut ed = ed_cell.borrow_mut();
   let ctx = ed.ctx_mut();
 
-  let q = quality.unwrap_or(1);
-  let new_ctx = sample_rates::resample(ctx,rate,q);
-  ed.push_new(new_ctx);
+  let new_ctx = match quality {
+    Some(LuaValue::Table(t)) => {
+      let d = Quality::default();
+      let q = Quality {
+        taps:t.get::<_,Option<usize>>("taps")?.unwrap_or(d.taps),
+        passband:t.get::<_,Option<f64>>("passband")?.unwrap_or(d.passband),
+        stopband:t.get::<_,Option<f64>>("stopband")?.unwrap_or(d.stopband)
+      };
+
+      sample_rates::resample_with(ctx,rate,&q)
+    },
+    q => {
+      let q = Option::<usize>::from_lua(q.unwrap_or(LuaNil),l)?.unwrap_or(1);
+      sample_rates::resample(ctx,rate,q)
+    }
+  };
+
+  ed.push_new(new_ctx,format!("resample to {}",rate));
   Ok(())
 }
 
@@ -18,8 +36,61 @@ pub fn pitch(l:&Lua,(ratio,quality):(f64,Option<usize>)) -> LuaResult<()> {
   let ctx = ed.ctx_mut();
 
   let q = quality.unwrap_or(1);
+  let label = format!("pitch {} on {}",ratio,ctx.channel_label());
   let new_ctx = sample_rates::pitch(ctx,ratio,q);
-  ed.push_new(new_ctx);
+  ed.push_new(new_ctx,label);
+  Ok(())
+}
+
+
+pub fn stretch(l:&Lua,ratio:f64) -> LuaResult<()> {
+  let ed_cell = &mut super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx_mut();
+
+  //nothing would come out the other end, which would just delete the selection
+  if ratio <= 0.0 || ratio.is_nan() {
+    return Err(format!("can't stretch by {}, it has to be more than 0",ratio)).into_lua_err();
+  }
+
+  let label = format!("stretch {} on {}",ratio,ctx.channel_label());
+  let new_ctx = sample_rates::stretch(ctx,ratio);
+  ed.push_new(new_ctx,label);
+  Ok(())
+}
+
+//len is in samples, whatever's selected gets stretched to fit it
+pub fn stretch_to(l:&Lua,len:f64) -> LuaResult<()> {
+  let ed_cell = &mut super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx_mut();
+
+  let (start,end) = ctx.sample_region();
+  if end <= start {
+    return Err("nothing selected to stretch".to_string()).into_lua_err();
+  }
+
+  if len < 1.0 || len.is_nan() {
+    return Err(format!("can't stretch to {} samples, it has to be at least 1",len)).into_lua_err();
+  }
+
+  let ratio = len/(end - start) as f64;
+  let label = format!("stretch to {} on {}",len,ctx.channel_label());
+  let new_ctx = sample_rates::stretch(ctx,ratio);
+  ed.push_new(new_ctx,label);
   Ok(())
 }
 
+//like pitch_shift but in semitones and cents, and the length stays put
+pub fn transpose(l:&Lua,(semitones,cents,quality):(f64,Option<f64>,Option<usize>)) -> LuaResult<()> {
+  let ed_cell = &mut super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx_mut();
+
+  let q = quality.unwrap_or(1);
+  let cents = cents.unwrap_or(0.0);
+  let label = format!("transpose {} st {} ct on {}",semitones,cents,ctx.channel_label());
+  let new_ctx = sample_rates::shift(ctx,crate::dsp::pitch_ratio(semitones,cents),q);
+  ed.push_new(new_ctx,label);
+  Ok(())
+}
@@ -0,0 +1,68 @@
+use mlua::prelude::*;
+use crate::edit::silence::{self,Detect};
+
+//thresholds are dBFS and lengths are seconds, the defaults
+//are -60dB and a tenth of a second
+fn detect(ctx:&crate::edit::Ctx,threshold:Option<f32>,min_secs:Option<f64>) -> Detect {
+  Detect::new(ctx,threshold.unwrap_or(-60.0),min_secs.unwrap_or(0.1))
+}
+
+fn regions(l:&Lua,found:Vec<(usize,usize)>) -> LuaResult<LuaTable> {
+  let out = l.create_table()?;
+
+  for (s,e) in found {
+    let t = l.create_table()?;
+    t.set("start",s)?;
+    t.set("finish",e)?;
+    out.push(t)?;
+  }
+
+  Ok(out)
+}
+
+//the silent bits of the selection as a list of {start=,finish=}
+pub fn silences(l:&Lua,(threshold,min_secs):(Option<f32>,Option<f64>)) -> LuaResult<LuaTable> {
+  let ed_cell = super::grab_editor(l)?;
+  let ed = ed_cell.borrow();
+  let ctx = ed.ctx();
+
+  regions(l,silence::silences(ctx,&detect(ctx,threshold,min_secs)))
+}
+
+//and everything that isn't silent
+pub fn sounds(l:&Lua,(threshold,min_secs):(Option<f32>,Option<f64>)) -> LuaResult<LuaTable> {
+  let ed_cell = super::grab_editor(l)?;
+  let ed = ed_cell.borrow();
+  let ctx = ed.ctx();
+
+  regions(l,silence::sounds(ctx,&detect(ctx,threshold,min_secs)))
+}
+
+pub fn trim_silence(l:&Lua,(threshold,min_secs):(Option<f32>,Option<f64>)) -> LuaResult<()> {
+  let ed_cell = super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx();
+
+  let label = format!("trim silence on {}",ctx.channel_label());
+  if let Some(new_ctx) = silence::trim(ctx,&detect(ctx,threshold,min_secs)) {
+    ed.push_new(new_ctx,label);
+  }
+
+  Ok(())
+}
+
+//gaps longer than max_secs get cut down to it
+pub fn shorten_silence(l:&Lua,(max_secs,threshold,min_secs):(f64,Option<f32>,Option<f64>)) -> LuaResult<()> {
+  let ed_cell = super::grab_editor(l)?;
+  let mut ed = ed_cell.borrow_mut();
+  let ctx = ed.ctx();
+
+  let max_len = (max_secs.max(0.0) * ctx.snd.sample_rate() as f64).round() as usize;
+  let label = format!("shorten silence to {}s on {}",max_secs,ctx.channel_label());
+
+  if let Some(new_ctx) = silence::shorten_gaps(ctx,&detect(ctx,threshold,min_secs),max_len) {
+    ed.push_new(new_ctx,label);
+  }
+
+  Ok(())
+}
@@ -8,7 +8,7 @@ use mlua::prelude::*;
 
 use crate::{
   edit::Editor,
-  snd::Snd
+  snd::{Snd,Format}
 };
 
 mod edit_userdata;
@@ -37,7 +37,7 @@ pub fn setup() -> LuaResult<mlua::Lua> {
 pub enum Action {
   Play,
   ActivateCmdLine,
-  NewWindow(Arc<Snd>,Option<String>),
+  NewWindow(Arc<Snd>,Option<String>,Format),
   ConfigAudio
 }
 
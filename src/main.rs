@@ -21,7 +21,7 @@ mod win_manager;
 mod sys_commands;
 mod skin;
 
-use snd::Snd;
+use snd::{Snd,Format};
 use sys_commands::SysCommand;
 
 fn main() {
@@ -52,7 +52,7 @@ struct Main {
 #[derive(Clone,Debug)]
 pub enum Msg {
   WinMsg(window::Id,win_manager::WindowMsg),
-  WinOpen(window::Id,Arc<Snd>,Option<String>),
+  WinOpen(window::Id,Arc<Snd>,Option<String>,Format),
   ConfOpen(window::Id),
   WinClosed(window::Id),
   Poll
@@ -70,7 +70,7 @@ impl Main {
     let (_id,open) = window::open(WINSET);
     (
       Self { audio,lua,mgr:Default::default()},
-      open.map( move |id|Msg::WinOpen(id,snd.clone(),None))
+      open.map( move |id|Msg::WinOpen(id,snd.clone(),None,Format::default()))
     )
   }
 
@@ -94,10 +94,10 @@ impl Main {
         Task::none()
       },
 
-      Some(SysCommand::OpenEditor(snd,file))=>{
+      Some(SysCommand::OpenEditor(snd,file,fmt))=>{
         let (_id,open) = window::open(WINSET);
         //it's annoying we have to copy the string
-        open.map(move|id|Msg::WinOpen(id,snd.clone(),file.clone()))
+        open.map(move|id|Msg::WinOpen(id,snd.clone(),file.clone(),fmt))
       },
 
       Some(SysCommand::OpenAudioConfig) => {
@@ -122,8 +122,8 @@ impl Main {
         self.handle_sys_cmd(cmd)
       },
 
-      Msg::WinOpen(id,snd,file) => {
-        self.mgr.open_editor(id,snd,file).expect("couldn't open editor");
+      Msg::WinOpen(id,snd,file,fmt) => {
+        self.mgr.open_editor(id,snd,file,fmt).expect("couldn't open editor");
         Task::none()
       }
 
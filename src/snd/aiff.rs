@@ -0,0 +1,400 @@
+use std::path::Path;
+use std::io::{Read,Seek,SeekFrom,Write};
+
+use dasp::Sample;
+use anyhow::{anyhow,Result};
+
+use super::{Snd,Format};
+use crate::blocks;
+use crate::dsp::{Dither,Quantizer};
+use blocks::BlockSequence as Seq;
+
+//the different ways aiff files lay out their samples
+#[derive(Debug,Clone,Copy,PartialEq)]
+enum Codec {
+  BigInt,
+  LittleInt, //aka 'sowt', it's what macs write
+  Float32,
+  Float64
+}
+
+impl Codec {
+  fn from_tag(tag:&[u8]) -> Result<Self> {
+    match tag {
+      b"NONE" | b"twos" => Ok(Codec::BigInt),
+      b"sowt" => Ok(Codec::LittleInt),
+      b"fl32" | b"FL32" => Ok(Codec::Float32),
+      b"fl64" | b"FL64" => Ok(Codec::Float64),
+      _ => Err(anyhow!("unsupported aiff compression type {}",String::from_utf8_lossy(tag)))
+    }
+  }
+
+  fn tag(&self) -> (&'static [u8;4],&'static str) {
+    match self {
+      Codec::BigInt => (b"NONE","not compressed"),
+      Codec::LittleInt => (b"sowt",""),
+      Codec::Float32 => (b"fl32","32-bit floating point"),
+      Codec::Float64 => (b"fl64","64-bit floating point")
+    }
+  }
+}
+
+struct Comm {
+  channels:usize,
+  frames:usize,
+  bits:usize,
+  rate:f64,
+  codec:Codec
+}
+
+impl Comm {
+  fn parse(buf:&[u8],aifc:bool) -> Result<Self> {
+    if buf.len() < 18 {
+      return Err(anyhow!("aiff COMM chunk is too short"));
+    }
+
+    let codec = if aifc && buf.len() >= 22 {
+      Codec::from_tag(&buf[18..22])?
+    }
+    else {
+      Codec::BigInt
+    };
+
+    let comm = Self {
+      channels:u16::from_be_bytes([buf[0],buf[1]]) as usize,
+      frames:u32::from_be_bytes([buf[2],buf[3],buf[4],buf[5]]) as usize,
+      bits:u16::from_be_bytes([buf[6],buf[7]]) as usize,
+      rate:read_extended(&buf[8..18]),
+      codec
+    };
+
+    //zero sized frames would never get anywhere reading the data
+    if comm.channels == 0 {
+      return Err(anyhow!("aiff file has no channels"));
+    }
+
+    if matches!(comm.codec,Codec::BigInt | Codec::LittleInt) && !(1..=32).contains(&comm.bits) {
+      return Err(anyhow!("unsupported aiff sample size of {} bits",comm.bits));
+    }
+
+    Ok(comm)
+  }
+
+  fn sample_bytes(&self) -> usize {
+    match self.codec {
+      Codec::Float32 => 4,
+      Codec::Float64 => 8,
+      _ => self.bits.div_ceil(8)
+    }
+  }
+
+  fn format(&self) -> Format {
+    match (self.codec,self.bits) {
+      (Codec::Float32 | Codec::Float64,_) => Format::Float32,
+      (_,b) if b <= 16 => Format::Int16,
+      (_,b) if b <= 24 => Format::Int24,
+      _ => Format::Int32
+    }
+  }
+
+  //ints are left justified, so putting them at the top of an i32
+  //means we can always scale by the same amount
+  fn decode(&self,b:&[u8]) -> f32 {
+    match self.codec {
+      Codec::BigInt => {
+        let v = b.iter().fold(0i32,|v,byte|(v << 8) | *byte as i32);
+        (v << (32 - 8*b.len())).to_sample::<f32>()
+      },
+
+      Codec::LittleInt => {
+        let v = b.iter().rev().fold(0i32,|v,byte|(v << 8) | *byte as i32);
+        (v << (32 - 8*b.len())).to_sample::<f32>()
+      },
+
+      Codec::Float32 => f32::from_be_bytes([b[0],b[1],b[2],b[3]]),
+
+      Codec::Float64 => {
+        f64::from_be_bytes([b[0],b[1],b[2],b[3],b[4],b[5],b[6],b[7]]) as f32
+      }
+    }
+  }
+}
+
+//aiff stores the sample rate as an 80 bit "extended" float
+fn read_extended(b:&[u8]) -> f64 {
+  let exp = (u16::from_be_bytes([b[0],b[1]]) & 0x7FFF) as i32;
+  let mant = u64::from_be_bytes([b[2],b[3],b[4],b[5],b[6],b[7],b[8],b[9]]);
+
+  if exp == 0 && mant == 0 {
+    0.0
+  }
+  else {
+    mant as f64 * 2f64.powi(exp - 16383 - 63)
+  }
+}
+
+fn write_extended(rate:usize) -> [u8;10] {
+  let mut out = [0u8;10];
+  let r = rate as u64;
+
+  if r == 0 {
+    return out;
+  }
+
+  let shift = r.leading_zeros();
+  let exp = (16383 + 63 - shift) as u16;
+  out[0..2].copy_from_slice(&exp.to_be_bytes());
+  out[2..10].copy_from_slice(&(r << shift).to_be_bytes());
+  out
+}
+
+pub fn load_aiff<P:AsRef<Path>>(p:P) -> Result<(Snd,Format)> {
+  let mut r = std::io::BufReader::new(std::fs::File::open(p)?);
+
+  let mut hdr = [0u8;12];
+  r.read_exact(&mut hdr)?;
+
+  let aifc = match (&hdr[0..4],&hdr[8..12]) {
+    (b"FORM",b"AIFF") => false,
+    (b"FORM",b"AIFC") => true,
+    _ => Err(anyhow!("this isn't an aiff file"))?
+  };
+
+  let mut comm = None;
+  let mut ssnd = None;
+
+  //walk the chunks, we only care about the format and the samples
+  let mut ch = [0u8;8];
+  while r.read_exact(&mut ch).is_ok() {
+    let size = u32::from_be_bytes([ch[4],ch[5],ch[6],ch[7]]) as u64;
+    let padded = size + (size & 1);
+
+    match &ch[0..4] {
+      b"COMM" => {
+        let mut buf = vec![0u8;size as usize];
+        r.read_exact(&mut buf)?;
+        comm = Some(Comm::parse(&buf,aifc)?);
+        r.seek(SeekFrom::Current((padded - size) as i64))?;
+      },
+
+      b"SSND" => {
+        let mut offsets = [0u8;8];
+        r.read_exact(&mut offsets)?;
+        let offset = u32::from_be_bytes([offsets[0],offsets[1],offsets[2],offsets[3]]) as u64;
+        ssnd = Some(r.stream_position()? + offset);
+        r.seek(SeekFrom::Current(padded as i64 - 8))?;
+      },
+
+      _ => {
+        r.seek(SeekFrom::Current(padded as i64))?;
+      }
+    }
+  }
+
+  let comm = comm.ok_or(anyhow!("aiff file has no COMM chunk"))?;
+  let start = ssnd.ok_or(anyhow!("aiff file has no SSND chunk"))?;
+  r.seek(SeekFrom::Start(start))?;
+
+  let bytes = comm.sample_bytes();
+  let frame_bytes = bytes * comm.channels;
+
+  //the header can claim anything, so only reserve what the file could actually hold
+  let room = r.get_ref().metadata()?.len().saturating_sub(start) as usize / frame_bytes;
+  let mut chans : Vec<Vec<f32>> = (0..comm.channels).map(|_|Vec::with_capacity(comm.frames.min(room))).collect();
+
+  //pull a chunk of frames at a time so we don't need the whole file in memory twice
+  let mut buf = vec![0u8;frame_bytes * 4096];
+  let mut left = comm.frames;
+
+  while left > 0 {
+    let n = left.min(4096);
+    r.read_exact(&mut buf[..n*frame_bytes])?;
+
+    for frame in buf[..n*frame_bytes].chunks_exact(frame_bytes) {
+      for (c,smp) in frame.chunks_exact(bytes).enumerate() {
+        chans[c].push(comm.decode(smp));
+      }
+    }
+
+    left -= n;
+  }
+
+  let seqs = chans.into_iter().map(|c|{
+    let s : Seq = blocks::Block::data(c).into();
+    s
+  });
+
+  Ok((Snd::from_iter(comm.rate.round() as usize,seqs),comm.format()))
+}
+
+//pascal strings have a length byte in front, and the whole
+//thing has to come out to an even number of bytes
+fn pstring(s:&str) -> Vec<u8> {
+  let mut out = vec![s.len() as u8];
+  out.extend_from_slice(s.as_bytes());
+
+  if out.len() % 2 == 1 {
+    out.push(0);
+  }
+
+  out
+}
+
+//plain aiff is always big endian ints, aiff-c lets us write floats
+//and little endian ints (sowt)
+pub fn save_aiff<P:AsRef<Path>>(snd:&Snd,p:P,fmt:Format,dither:Dither,aifc:bool) -> Result<()> {
+  let channels = snd.channels();
+  let frames = snd.len();
+
+  let codec = match (fmt,aifc) {
+    (Format::Float32,_) => Codec::Float32,
+    (_,true) => Codec::LittleInt,
+    (_,false) => Codec::BigInt
+  };
+
+  let aifc = aifc || codec == Codec::Float32;
+  let bytes = (fmt.bits() / 8) as usize;
+  let data_len = frames * channels * bytes;
+
+  let (tag,name) = codec.tag();
+  let name = pstring(name);
+  let comm_len = if aifc { 18 + 4 + name.len() } else { 18 };
+  let fver_len = if aifc { 12 } else { 0 };
+  let form_len = 4 + fver_len + (8 + comm_len) + (8 + 8 + data_len + (data_len & 1));
+
+  //aiff has no 64 bit version, so past 4gb there's nothing we can write
+  if form_len > u32::MAX as usize {
+    return Err(anyhow!("this is too big for an aiff file, try saving as a wav"));
+  }
+
+  let mut w = std::io::BufWriter::new(std::fs::File::create(p)?);
+  w.write_all(b"FORM")?;
+  w.write_all(&(form_len as u32).to_be_bytes())?;
+  w.write_all(if aifc { b"AIFC" } else { b"AIFF" })?;
+
+  if aifc {
+    w.write_all(b"FVER")?;
+    w.write_all(&4u32.to_be_bytes())?;
+    w.write_all(&0xA2805140u32.to_be_bytes())?;
+  }
+
+  w.write_all(b"COMM")?;
+  w.write_all(&(comm_len as u32).to_be_bytes())?;
+  w.write_all(&(channels as u16).to_be_bytes())?;
+  w.write_all(&(frames as u32).to_be_bytes())?;
+  w.write_all(&fmt.bits().to_be_bytes())?;
+  w.write_all(&write_extended(snd.sample_rate()))?;
+
+  if aifc {
+    w.write_all(tag)?;
+    w.write_all(&name)?;
+  }
+
+  w.write_all(b"SSND")?;
+  w.write_all(&((8 + data_len) as u32).to_be_bytes())?;
+  w.write_all(&0u32.to_be_bytes())?; //offset
+  w.write_all(&0u32.to_be_bytes())?; //block size
+
+  let little = codec == Codec::LittleInt;
+  let mut q = Quantizer::new(dither,fmt.bits(),channels);
+
+  for (i,smp) in snd.interleaved_audio().enumerate() {
+    match fmt {
+      Format::Float32 => w.write_all(&smp.to_be_bytes())?,
+
+      Format::Int32 => {
+        let v = smp.to_sample::<i32>();
+        w.write_all(&if little { v.to_le_bytes() } else { v.to_be_bytes() })?
+      },
+
+      _ => {
+        let v = q.quantize(smp,i % channels);
+        if little {
+          w.write_all(&v.to_le_bytes()[..bytes])?
+        }
+        else {
+          w.write_all(&v.to_be_bytes()[4-bytes..])?
+        }
+      }
+    }
+  }
+
+  if data_len & 1 == 1 {
+    w.write_all(&[0])?;
+  }
+
+  w.flush()?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_extended_rates() {
+    for rate in [8000,22050,44100,48000,96000,192000] {
+      let ext = write_extended(rate);
+      assert_eq!(read_extended(&ext),rate as f64,"sample rates should survive the 80 bit float");
+    }
+
+    //this is what 44100 looks like in every aiff file ever
+    let ext = write_extended(44100);
+    assert_eq!(ext,[0x40,0x0E,0xAC,0x44,0,0,0,0,0,0],"44100 should match the usual bytes");
+  }
+
+  #[test]
+  fn test_pstrings() {
+    assert_eq!(pstring(""),vec![0,0],"empty strings get padded");
+    assert_eq!(pstring("ab"),vec![2,b'a',b'b',0],"odd lengths get padded");
+    assert_eq!(pstring("abc"),vec![3,b'a',b'b',b'c'],"even lengths don't");
+  }
+
+  #[test]
+  fn test_bad_comm() {
+    let comm = |channels:u16,bits:u16|{
+      let mut b = channels.to_be_bytes().to_vec();
+      b.extend(100u32.to_be_bytes());
+      b.extend(bits.to_be_bytes());
+      b.extend(write_extended(44100));
+      Comm::parse(&b,false)
+    };
+
+    assert!(comm(2,16).is_ok(),"a normal COMM chunk should parse");
+    assert!(comm(0,16).is_err(),"no channels should be an error");
+    assert!(comm(2,0).is_err(),"zero bit samples should be an error");
+    assert!(comm(2,48).is_err(),"so should anything past 32 bits");
+  }
+
+  #[test]
+  fn test_round_trip() {
+    let path = std::env::temp_dir().join("ksnd_aiff_round_trip.aiff");
+    let smps : Vec<f32> = (0..1001).map(|i|(i as f32 / 1001.0) - 0.5).collect();
+    let seqs = vec![blocks::Block::data(smps.clone()).into(),blocks::Block::data(smps.iter().map(|s|-s).collect()).into()];
+    let snd = Snd::new(22050,seqs);
+
+    //plain big endian ints, sowt and fl32
+    for (fmt,aifc) in [(Format::Int16,false),(Format::Int24,false),(Format::Int16,true),(Format::Int24,true),(Format::Float32,true)] {
+      save_aiff(&snd,&path,fmt,Dither::Off,aifc).unwrap();
+      let (back,back_fmt) = load_aiff(&path).unwrap();
+
+      assert_eq!(back_fmt,fmt,"aiff files should remember their format");
+      assert_eq!(back.sample_rate(),22050,"the sample rate should survive the trip");
+      assert_eq!(back.channels(),2,"both channels should come back");
+      assert_eq!(back.len(),1001,"every frame should come back");
+
+      for (a,b) in snd.interleaved_audio().zip(back.interleaved_audio()) {
+        assert!((a - b).abs() < 1e-4,"aiff samples should survive the trip");
+      }
+    }
+
+    //a header claiming way more frames than there are should be an error, not an abort
+    let mut bytes = std::fs::read(&path).unwrap();
+    let comm = bytes.windows(4).position(|w|w == b"COMM").unwrap();
+    bytes[comm+10..comm+14].copy_from_slice(&u32::MAX.to_be_bytes());
+    std::fs::write(&path,&bytes).unwrap();
+    assert!(load_aiff(&path).is_err(),"a lying frame count should be an error");
+
+    std::fs::remove_file(&path).ok();
+  }
+}
@@ -0,0 +1,512 @@
+use std::path::Path;
+use std::io::Write;
+
+use anyhow::{anyhow,Result};
+
+use super::{Snd,Format};
+use crate::blocks;
+use crate::dsp::{Dither,Quantizer};
+use blocks::BlockSequence as Seq;
+
+pub fn load_flac<P:AsRef<Path>>(p:P) -> Result<(Snd,Format)> {
+  let mut r = claxon::FlacReader::open(p)?;
+  let info = r.streaminfo();
+  let channels = info.channels as usize;
+
+  let fmt = match info.bits_per_sample {
+    b if b <= 16 => Format::Int16,
+    b if b <= 24 => Format::Int24,
+    _ => Format::Int32
+  };
+
+  let scale = 1.0 / (1u64 << (info.bits_per_sample - 1)) as f32;
+  let cap = info.samples.unwrap_or(0) as usize;
+  let mut chans : Vec<Vec<f32>> = (0..channels).map(|_|Vec::with_capacity(cap)).collect();
+
+  for (i,smp) in r.samples().enumerate() {
+    chans[i % channels].push(smp? as f32 * scale);
+  }
+
+  let seqs = chans.into_iter().map(|c|{
+    let s : Seq = blocks::Block::data(c).into();
+    s
+  });
+
+  Ok((Snd::from_iter(info.sample_rate as usize,seqs),fmt))
+}
+
+//flac only does 16 and 24 bit integers here, anything else has to be
+//asked for explicitly rather than quietly getting written as something else
+pub fn save_flac<P:AsRef<Path>>(snd:&Snd,p:P,fmt:Format,dither:Dither,level:u8) -> Result<()> {
+  let bits : u32 = match fmt {
+    Format::Int16 => 16,
+    Format::Int24 => 24,
+    _ => return Err(anyhow!("flac can't hold {} samples, save it as 16 or 24 bit",fmt))
+  };
+  let channels = snd.channels();
+
+  if channels == 0 || channels > 8 {
+    return Err(anyhow!("flac can only hold 1 to 8 channels, this has {}",channels));
+  }
+
+  let cfg = Level::new(level);
+  let len = snd.len();
+
+  let mut out = std::io::BufWriter::new(std::fs::File::create(p)?);
+  out.write_all(b"fLaC")?;
+  out.write_all(&stream_info(snd,bits,cfg.block_size))?;
+
+  let mut q = Quantizer::new(dither,bits as u16,channels);
+  let mut smps = snd.interleaved_audio().enumerate().map(|(i,s)|q.quantize(s,i % channels));
+  let mut bufs = vec![vec![0i64;cfg.block_size];channels];
+  let mut frame_no = 0u64;
+  let mut done = 0;
+
+  while done < len {
+    let n = cfg.block_size.min(len - done);
+
+    for i in 0..n {
+      for buf in bufs.iter_mut() {
+        buf[i] = smps.next().unwrap_or(0) as i64;
+      }
+    }
+
+    let chans : Vec<&[i64]> = bufs.iter().map(|b|&b[..n]).collect();
+    out.write_all(&frame(frame_no,&chans,bits,&cfg))?;
+
+    frame_no += 1;
+    done += n;
+  }
+
+  out.flush()?;
+  Ok(())
+}
+
+//this is roughly how the reference encoder's levels go, without
+//the lpc stuff we only get to play with the block size, how high
+//a fixed predictor we try, and how finely we split up the rice partitions
+struct Level {
+  block_size:usize,
+  max_order:usize,
+  max_partition:u32,
+  stereo:bool
+}
+
+impl Level {
+  fn new(level:u8) -> Self {
+    let level = level.min(8);
+
+    Self {
+      block_size:if level < 3 { 1152 } else { 4096 },
+      max_order:if level == 0 { 2 } else { 4 },
+      max_partition:3 + level as u32/3,
+      stereo:level > 0
+    }
+  }
+}
+
+fn stream_info(snd:&Snd,bits:u32,block_size:usize) -> Vec<u8> {
+  let len = snd.len() as u64;
+  let mut w = BitWriter::default();
+
+  //last metadata block, type 0 (streaminfo), 34 bytes long
+  w.write(1,1);
+  w.write(0,7);
+  w.write(34,24);
+
+  let min_block = (block_size as u64).min(len).max(16);
+  w.write(min_block,16);
+  w.write(block_size as u64,16);
+  w.write(0,24); //min frame size, unknown
+  w.write(0,24); //max frame size, unknown
+  w.write(snd.sample_rate() as u64,20);
+  w.write(snd.channels() as u64 - 1,3);
+  w.write(bits as u64 - 1,5);
+  w.write(len >> 32,4);
+  w.write(len & 0xFFFFFFFF,32);
+
+  //an all zero md5 means "we didn't bother"
+  for _ in 0..4 {
+    w.write(0,32);
+  }
+
+  w.bytes
+}
+
+fn frame(frame_no:u64,chans:&[&[i64]],bits:u32,cfg:&Level) -> Vec<u8> {
+  let n = chans[0].len();
+
+  let (assignment,plans) = if chans.len() == 2 && cfg.stereo {
+    stereo_plans(chans[0],chans[1],bits,cfg)
+  }
+  else {
+    let plans = chans.iter().map(|c|(bits,Subframe::plan(c,bits,cfg))).collect();
+    (chans.len() as u64 - 1,plans)
+  };
+
+  let mut w = BitWriter::default();
+  w.write(0b11111111111110,14);
+  w.write(0,1);
+  w.write(0,1); //fixed block size stream
+  w.write(0b0111,4); //block size is a 16 bit number at the end of the header
+  w.write(0b0000,4); //sample rate comes from the streaminfo
+  w.write(assignment,4);
+  w.write(if bits == 16 { 0b100 } else { 0b110 },3);
+  w.write(0,1);
+  w.write_utf8(frame_no);
+  w.write(n as u64 - 1,16);
+
+  let crc = crc8(&w.bytes);
+  w.write(crc as u64,8);
+
+  for (bps,sub) in plans.iter() {
+    sub.write(&mut w,*bps);
+  }
+
+  w.align();
+  let crc = crc16(&w.bytes);
+  w.write(crc as u64,16);
+  w.bytes
+}
+
+//tries all 4 ways of coding a stereo pair and keeps the smallest,
+//along with the channel assignment code that goes in the frame header
+fn stereo_plans(left:&[i64],right:&[i64],bits:u32,cfg:&Level) -> (u64,Vec<(u32,Subframe)>) {
+  let side : Vec<i64> = left.iter().zip(right).map(|(l,r)|l-r).collect();
+  let mid : Vec<i64> = left.iter().zip(right).map(|(l,r)|(l+r)>>1).collect();
+
+  let l = Subframe::plan(left,bits,cfg);
+  let r = Subframe::plan(right,bits,cfg);
+  let s = Subframe::plan(&side,bits+1,cfg);
+  let m = Subframe::plan(&mid,bits,cfg);
+
+  let costs = [
+    l.cost + r.cost,
+    l.cost + s.cost,
+    s.cost + r.cost,
+    m.cost + s.cost
+  ];
+
+  let best = (0..4).min_by_key(|i|costs[*i]).unwrap_or(0);
+
+  match best {
+    1 => (0b1000,vec![(bits,l),(bits+1,s)]),
+    2 => (0b1001,vec![(bits+1,s),(bits,r)]),
+    3 => (0b1010,vec![(bits,m),(bits+1,s)]),
+    _ => (0b0001,vec![(bits,l),(bits,r)])
+  }
+}
+
+enum Coding {
+  Constant(i64),
+  Verbatim(Vec<i64>),
+  Fixed {
+    warmup:Vec<i64>,
+    partition:u32,
+    params:Vec<u32>,
+    residual:Vec<i64>
+  }
+}
+
+struct Subframe {
+  cost:usize,
+  coding:Coding
+}
+
+impl Subframe {
+  fn plan(x:&[i64],bps:u32,cfg:&Level) -> Self {
+    let n = x.len();
+    let bps = bps as usize;
+
+    if x.iter().all(|s|*s == x[0]) {
+      return Self{ cost:8 + bps, coding:Coding::Constant(x[0]) };
+    }
+
+    let mut best = Self {
+      cost:8 + n*bps,
+      coding:Coding::Verbatim(x.to_vec())
+    };
+
+    for order in 0..=cfg.max_order.min(n-1) {
+      let residual = fixed_residual(x,order);
+
+      for partition in 0..=cfg.max_partition {
+        let parts = 1 << partition;
+        if n % parts != 0 || n/parts <= order {
+          break;
+        }
+
+        let (bits,params) = rice_partitions(&residual,n/parts,order,parts);
+        let cost = 8 + order*bps + 6 + bits;
+
+        if cost < best.cost {
+          best = Self {
+            cost,
+            coding:Coding::Fixed {
+              warmup:x[..order].to_vec(),
+              partition,
+              params,
+              residual:residual.clone()
+            }
+          };
+        }
+      }
+    }
+
+    best
+  }
+
+  fn write(&self,w:&mut BitWriter,bps:u32) {
+    let bps = bps as usize;
+    w.write(0,1);
+
+    match &self.coding {
+      Coding::Constant(v) => {
+        w.write(0b000000,6);
+        w.write(0,1);
+        w.write_signed(*v,bps);
+      },
+
+      Coding::Verbatim(x) => {
+        w.write(0b000001,6);
+        w.write(0,1);
+        for s in x {
+          w.write_signed(*s,bps);
+        }
+      },
+
+      Coding::Fixed{warmup,partition,params,residual} => {
+        w.write(0b001000 | warmup.len() as u64,6);
+        w.write(0,1);
+
+        for s in warmup {
+          w.write_signed(*s,bps);
+        }
+
+        w.write(0b00,2); //4 bit rice params
+        w.write(*partition as u64,4);
+
+        let parts = params.len();
+        let part_len = (residual.len() + warmup.len())/parts;
+        let mut start = 0;
+
+        for (i,k) in params.iter().enumerate() {
+          let count = if i == 0 { part_len - warmup.len() } else { part_len };
+          w.write(*k as u64,4);
+
+          for r in &residual[start..start+count] {
+            w.write_rice(zigzag(*r),*k);
+          }
+
+          start += count;
+        }
+      }
+    }
+  }
+}
+
+fn fixed_residual(x:&[i64],order:usize) -> Vec<i64> {
+  (order..x.len()).map(|i|{
+    match order {
+      0 => x[i],
+      1 => x[i] - x[i-1],
+      2 => x[i] - 2*x[i-1] + x[i-2],
+      3 => x[i] - 3*x[i-1] + 3*x[i-2] - x[i-3],
+      _ => x[i] - 4*x[i-1] + 6*x[i-2] - 4*x[i-3] + x[i-4]
+    }
+  }).collect()
+}
+
+fn zigzag(r:i64) -> u64 {
+  if r >= 0 { (r as u64) << 1 } else { ((-r as u64) << 1) - 1 }
+}
+
+fn rice_bits(vals:&[i64],k:u32) -> usize {
+  vals.iter().map(|r|(zigzag(*r) >> k) as usize + 1 + k as usize).sum()
+}
+
+//picks a rice parameter for each partition, the first
+//partition is short by the predictor order
+fn rice_partitions(residual:&[i64],part_len:usize,order:usize,parts:usize) -> (usize,Vec<u32>) {
+  let mut start = 0;
+  let mut total = 0;
+  let mut params = Vec::with_capacity(parts);
+
+  for i in 0..parts {
+    let count = if i == 0 { part_len - order } else { part_len };
+    let vals = &residual[start..start+count];
+    start += count;
+
+    let mean = vals.iter().map(|r|zigzag(*r)).sum::<u64>() / count.max(1) as u64;
+    let guess = if mean == 0 { 0 } else { mean.ilog2().min(14) };
+
+    let (bits,k) = [guess.saturating_sub(1),guess,(guess+1).min(14)].into_iter()
+    .map(|k|(rice_bits(vals,k),k))
+    .min()
+    .unwrap_or((0,0));
+
+    total += 4 + bits;
+    params.push(k);
+  }
+
+  (total,params)
+}
+
+#[derive(Default)]
+struct BitWriter {
+  bytes:Vec<u8>,
+  acc:u64,
+  count:u32
+}
+
+impl BitWriter {
+  fn write(&mut self,val:u64,bits:u32) {
+    if bits == 0 {
+      return;
+    }
+
+    let mask = if bits == 64 { u64::MAX } else { (1 << bits) - 1 };
+    self.acc = (self.acc << bits) | (val & mask);
+    self.count += bits;
+
+    while self.count >= 8 {
+      self.count -= 8;
+      self.bytes.push((self.acc >> self.count) as u8);
+    }
+
+    self.acc &= (1 << self.count) - 1;
+  }
+
+  fn write_signed(&mut self,val:i64,bits:usize) {
+    self.write(val as u64,bits as u32);
+  }
+
+  fn write_rice(&mut self,u:u64,k:u32) {
+    let mut q = u >> k;
+
+    while q >= 32 {
+      self.write(0,32);
+      q -= 32;
+    }
+
+    self.write(1,q as u32 + 1);
+    self.write(u,k);
+  }
+
+  //frame numbers get stored with the same scheme as utf-8 characters
+  fn write_utf8(&mut self,v:u64) {
+    if v < 0x80 {
+      self.write(v,8);
+      return;
+    }
+
+    let bits = 64 - v.leading_zeros();
+    let n = match bits {
+      0..=11 => 2,
+      12..=16 => 3,
+      17..=21 => 4,
+      22..=26 => 5,
+      27..=31 => 6,
+      _ => 7
+    };
+
+    let lead = (0xFF00u64 >> n) & 0xFF;
+    self.write(lead | (v >> (6*(n-1))),8);
+
+    for i in (0..n-1).rev() {
+      self.write(0x80 | ((v >> (6*i)) & 0x3F),8);
+    }
+  }
+
+  fn align(&mut self) {
+    if self.count > 0 {
+      self.write(0,8 - self.count);
+    }
+  }
+}
+
+fn crc8(bytes:&[u8]) -> u8 {
+  bytes.iter().fold(0u8,|crc,b|{
+    let mut crc = crc ^ b;
+    for _ in 0..8 {
+      crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+    }
+    crc
+  })
+}
+
+fn crc16(bytes:&[u8]) -> u16 {
+  bytes.iter().fold(0u16,|crc,b|{
+    let mut crc = crc ^ ((*b as u16) << 8);
+    for _ in 0..8 {
+      crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+    }
+    crc
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_utf8_frame_numbers() {
+    let mut w = BitWriter::default();
+    w.write_utf8(0x7F);
+    assert_eq!(w.bytes,vec![0x7F],"small numbers are a single byte");
+
+    let mut w = BitWriter::default();
+    w.write_utf8(0x80);
+    assert_eq!(w.bytes,vec![0xC2,0x80],"bigger numbers get a lead byte");
+
+    let mut w = BitWriter::default();
+    w.write_utf8(0x20AC);
+    assert_eq!(w.bytes,vec![0xE2,0x82,0xAC],"3 byte numbers should match utf-8");
+  }
+
+  #[test]
+  fn test_fixed_residual() {
+    let ramp : Vec<i64> = (0..10).map(|i|i*3).collect();
+    assert!(fixed_residual(&ramp,2).iter().all(|r|*r == 0),"a ramp should be perfectly predicted by order 2");
+
+    let plan = Subframe::plan(&ramp,16,&Level::new(5));
+    assert!(matches!(plan.coding,Coding::Fixed{..}),"a ramp should use a fixed predictor");
+  }
+
+  #[test]
+  fn test_round_trip() {
+    //10000 doesn't divide into either block size
+    for (channels,fmt,level) in [(1,Format::Int16,0),(2,Format::Int16,5),(1,Format::Int24,8),(2,Format::Int24,3)] {
+      let full = (1i64 << (fmt.bits() - 1)) as f32;
+      let seqs = (0..channels).map(|c|{
+        let samples : Vec<f32> = (0..10000).map(|i|{
+          let v = ((i as f32 * 0.01 * (c + 1) as f32).sin() * 0.8 * full).round();
+          v/full
+        }).collect();
+
+        let s : Seq = blocks::Block::data(samples).into();
+        s
+      });
+
+      let snd = Snd::from_iter(44100,seqs);
+      let path = std::env::temp_dir().join(format!("ksnd_flac_{}_{}_{}.flac",channels,fmt.bits(),level));
+      save_flac(&snd,&path,fmt,Dither::Off,level).unwrap();
+      let (back,back_fmt) = load_flac(&path).unwrap();
+      std::fs::remove_file(&path).ok();
+
+      assert_eq!(back_fmt,fmt,"the bit depth should come back");
+      assert_eq!(back.len(),10000,"the length should come back");
+      assert_eq!(back.channels(),channels,"the channels should come back");
+
+      for (a,b) in snd.seqs().iter().zip(back.seqs().iter()) {
+        let same = a.samples(..).zip(b.samples(..)).all(|(x,y)|x == y);
+        assert!(same,"{} channel {} bit flac at level {} should be lossless",channels,fmt.bits(),level);
+      }
+    }
+
+    let snd = Snd::from_iter(44100,[blocks::Block::data(vec![0.0;10]).into()]);
+    let path = std::env::temp_dir().join("ksnd_flac_float.flac");
+    assert!(save_flac(&snd,&path,Format::Float32,Dither::Off,5).is_err(),"floats shouldn't quietly become 24 bit");
+    std::fs::remove_file(&path).ok();
+  }
+}
@@ -0,0 +1,99 @@
+use anyhow::{anyhow,Result};
+
+//this is the sample format a sound gets written out as,
+//it's kept around by the editor so saving a file doesn't
+//squash a 24 bit recording down to 16 bits
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum Format {
+  #[default]
+  Int16,
+  Int24,
+  Int32,
+  Float32
+}
+
+impl Format {
+  pub fn bits(&self) -> u16 {
+    match self {
+      Format::Int16 => 16,
+      Format::Int24 => 24,
+      Format::Int32 => 32,
+      Format::Float32 => 32
+    }
+  }
+
+  pub fn is_float(&self) -> bool {
+    matches!(self,Format::Float32)
+  }
+
+  //this is only what it gets saved as, odd depths go up to the next size.
+  //loading scales by the file's own bit depth
+  pub fn from_spec(spec:&hound::WavSpec) -> Result<Self> {
+    match (spec.sample_format,spec.bits_per_sample) {
+      (hound::SampleFormat::Float,32) => Ok(Format::Float32),
+      (hound::SampleFormat::Int,i) if i <= 16 => Ok(Format::Int16),
+      (hound::SampleFormat::Int,i) if i <= 24 => Ok(Format::Int24),
+      (hound::SampleFormat::Int,i) if i <= 32 => Ok(Format::Int32),
+      _ => Err(anyhow!("some kinda weird wav format here"))
+    }
+  }
+
+  pub fn wav_spec(&self,channels:u16,sample_rate:u32) -> hound::WavSpec {
+    let sample_format = if self.is_float() {
+      hound::SampleFormat::Float
+    }
+    else {
+      hound::SampleFormat::Int
+    };
+
+    hound::WavSpec {
+      channels,
+      sample_rate,
+      bits_per_sample: self.bits(),
+      sample_format
+    }
+  }
+}
+
+impl std::str::FromStr for Format {
+  type Err = anyhow::Error;
+
+  fn from_str(s:&str) -> Result<Self> {
+    match s {
+      "16" | "i16" => Ok(Format::Int16),
+      "24" | "i24" => Ok(Format::Int24),
+      "32" | "i32" => Ok(Format::Int32),
+      "f32" | "float" => Ok(Format::Float32),
+      _ => Err(anyhow!("unknown sample format {}, try 16, 24, 32 or f32",s))
+    }
+  }
+}
+
+impl std::fmt::Display for Format {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Format::Float32 => write!(f,"32 bit float"),
+      _ => write!(f,"{} bit",self.bits())
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_spec_round_trip() {
+    for fmt in [Format::Int16,Format::Int24,Format::Int32,Format::Float32] {
+      let spec = fmt.wav_spec(2,44100);
+      assert_eq!(Format::from_spec(&spec).unwrap(),fmt,"formats should survive a trip through a wav spec");
+    }
+  }
+
+  #[test]
+  fn test_parse() {
+    assert_eq!("24".parse::<Format>().unwrap(),Format::Int24,"plain bit counts should parse");
+    assert_eq!("f32".parse::<Format>().unwrap(),Format::Float32,"float should parse");
+    assert!("12".parse::<Format>().is_err(),"junk formats should fail");
+  }
+}
@@ -3,10 +3,155 @@ use std::path::Path;
 use dasp::Sample;
 use anyhow::{anyhow,Result};
 
-use super::Snd;
+use super::{Snd,Format,Meta,flac,aiff,lossy,opus,meta};
 use crate::blocks;
+use crate::dsp::{Dither,Quantizer};
 use blocks::BlockSequence as Seq;
 
+//everything that gets decided about a file when it's saved,
+//the editor holds onto one of these so it's the same each time
+#[derive(Debug,Clone,Copy,Default)]
+pub struct SaveOpts {
+  pub format:Format,
+  pub dither:Dither,
+  pub flac_level:u8
+}
+
+//raw files have no header, so whoever's loading one has to tell us
+//what's in it
+#[derive(Debug,Clone,Copy)]
+pub struct RawLayout {
+  pub sample_rate:usize,
+  pub channels:usize,
+  pub format:Format,
+  pub big_endian:bool,
+  pub offset:u64
+}
+
+impl Default for RawLayout {
+  fn default() -> Self {
+    Self {
+      sample_rate:44100,
+      channels:1,
+      format:Format::Int16,
+      big_endian:false,
+      offset:0
+    }
+  }
+}
+
+//the file dialogs use these so they stay in sync with what we can open
+pub const EXTENSIONS : [&str;5] = ["wav","flac","aif","aiff","aifc"];
+
+//the lossy stuff can only be opened, not saved
+pub fn load_extensions() -> Vec<&'static str> {
+  let mut exts = EXTENSIONS.to_vec();
+
+  if cfg!(feature="lossy") {
+    exts.extend(["mp3","ogg","oga"]);
+  }
+
+  if cfg!(feature="opus") {
+    exts.push("opus");
+  }
+
+  exts
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+enum Kind {
+  Wav,
+  Flac,
+  Aiff,
+  Lossy,
+  Opus
+}
+
+fn extension(p:&Path) -> Option<String> {
+  p.extension().and_then(|e|e.to_str()).map(|e|e.to_lowercase())
+}
+
+fn kind_from_extension(p:&Path) -> Kind {
+  match extension(p).as_deref() {
+    Some("flac") => Kind::Flac,
+    Some("aif" | "aiff" | "aifc") => Kind::Aiff,
+    Some("mp3" | "ogg" | "oga") => Kind::Lossy,
+    Some("opus") => Kind::Opus,
+    _ => Kind::Wav
+  }
+}
+
+//files lie about their extensions all the time, so peek at the header first
+fn sniff(p:&Path) -> Result<Option<Kind>> {
+  use std::io::Read;
+
+  let mut hdr = [0u8;64];
+  let mut f = std::fs::File::open(p)?;
+  if f.read_exact(&mut hdr).is_err() {
+    return Ok(None);
+  }
+
+  //ogg is just a container, the first packet says what's inside
+  let opus = hdr.windows(8).any(|w|w == b"OpusHead");
+
+  let kind = match (&hdr[0..4],&hdr[8..12]) {
+    (b"RIFF" | b"RF64" | b"BW64",_) => Some(Kind::Wav),
+    (b"fLaC",_) => Some(Kind::Flac),
+    (b"FORM",b"AIFF" | b"AIFC") => Some(Kind::Aiff),
+    (b"OggS",_) if opus => Some(Kind::Opus),
+    (b"OggS",_) => Some(Kind::Lossy),
+    ([b'I',b'D',b'3',_],_) => Some(Kind::Lossy),
+    //an mpeg frame sync, for mp3s without tags
+    ([0xFF,b,_,_],_) if b & 0xE0 == 0xE0 => Some(Kind::Lossy),
+    _ => None
+  };
+
+  Ok(kind)
+}
+
+pub fn load<P:AsRef<Path>>(p:P) -> Result<(Snd,Format)> {
+  let kind = sniff(p.as_ref())?.unwrap_or(kind_from_extension(p.as_ref()));
+
+  match kind {
+    Kind::Flac => flac::load_flac(p),
+    Kind::Aiff => aiff::load_aiff(p),
+    Kind::Lossy => lossy::load_lossy(p),
+    Kind::Opus => opus::load_opus(p),
+    Kind::Wav => load_wav(p)
+  }
+}
+
+//when saving the extension is all we have to go on,
+//anything we don't recognize gets written as a wav.
+//only wavs have anywhere to put the metadata
+pub fn save<P:AsRef<Path>>(snd:&Snd,meta:&Meta,p:P,opts:&SaveOpts) -> Result<()> {
+  let p = p.as_ref();
+  let aifc = extension(p).as_deref() == Some("aifc");
+
+  match kind_from_extension(p) {
+    Kind::Flac => swap_in(p,|tmp|flac::save_flac(snd,tmp,opts.format,opts.dither,opts.flac_level)),
+    Kind::Aiff => swap_in(p,|tmp|aiff::save_aiff(snd,tmp,opts.format,opts.dither,aifc)),
+    Kind::Lossy | Kind::Opus => Err(anyhow!("lossy formats can only be opened, try saving as a wav or flac")),
+    Kind::Wav => swap_in(p,|tmp|save_wav(snd,tmp,opts.format,opts.dither,meta))
+  }
+}
+
+//big files can be getting read straight off of the file we're saving over,
+//so the new one gets written next to it and swapped in when it's done
+fn swap_in<F:FnOnce(&Path) -> Result<()>>(p:&Path,write:F) -> Result<()> {
+  let mut tmp = p.as_os_str().to_owned();
+  tmp.push(".tmp");
+  let tmp = std::path::PathBuf::from(tmp);
+
+  if let Err(e) = write(&tmp) {
+    std::fs::remove_file(&tmp).ok();
+    return Err(e);
+  }
+
+  std::fs::rename(&tmp,p)?;
+  Ok(())
+}
+
 fn extract_channels<T,R>(r:&mut hound::WavReader<R>) -> Vec<f32> 
 where
   T:hound::Sample + dasp::Sample + dasp::sample::ToSample<f32>,
@@ -15,27 +160,200 @@ where
   r.samples::<T>().map(|s|s.unwrap().to_sample::<f32>()).collect()
 }
 
-pub fn load_wav<P:AsRef<Path>>(p:P) -> Result<Snd> {
+//the audio is what matters, a broken cue or list chunk
+//shouldn't keep the file from opening
+fn read_meta(p:&Path) -> Meta {
+  meta::read_meta(p).unwrap_or_default()
+}
+
+//anything bigger than this stays on disk and gets read as it's needed
+const DISK_THRESHOLD : u64 = 256 * 1024 * 1024;
+
+pub fn load_wav<P:AsRef<Path>>(p:P) -> Result<(Snd,Format)> {
+  let p = p.as_ref();
+
+  //hound can't do rf64, and big files shouldn't be read in all at once
+  let hdr = read_header(p)?;
+  if hdr.rf64 || hdr.data_len > DISK_THRESHOLD {
+    return load_wav_direct(p,hdr);
+  }
+
   let mut r = hound::WavReader::open(p)?;
   let spec = r.spec();
   let channels = spec.channels as usize;
+  let fmt = Format::from_spec(&spec)?;
 
-  let samples = match(spec.sample_format,spec.bits_per_sample) {
-    (hound::SampleFormat::Float,_) => { 
+  //extensible files can say only some of the bits are used, like 20 out of 24.
+  //hound won't read those, but they're left justified so the container decodes fine
+  if spec.bits_per_sample % 8 != 0 {
+    return load_wav_direct(p,hdr);
+  }
+
+  let samples = match spec.sample_format {
+    hound::SampleFormat::Float => {
       extract_channels::<f32,_>(&mut r)
     },
 
-    (hound::SampleFormat::Int,i) if i <= 16 => {
-      extract_channels::<i16,_>(&mut r)
-    },
+    //ints come out of hound right justified at whatever depth the file is,
+    //so 8 bit files top out at 127 and 24 bit ones at 2^23
+    hound::SampleFormat::Int => {
+      let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+      r.samples::<i32>().map(|s|s.map(|v|v as f32 * scale)).collect::<Result<_,_>>()?
+    }
+  };
 
-    (hound::SampleFormat::Int,i) if i <= 32 => { 
-      extract_channels::<i32,_>(&mut r)
-    },
+  let meta = read_meta(p);
+  let snd = Snd::new(spec.sample_rate as usize,deinterleave(&samples,channels)).with_meta(meta);
+  Ok((snd,fmt))
+}
+
+//what we need to find and decode the samples without hound
+struct WavHeader {
+  spec:hound::WavSpec,
+  block_align:usize,
+  offset:u64,
+  data_len:u64,
+  rf64:bool
+}
+
+const WAVE_FORMAT_FLOAT : u16 = 3;
+const WAVE_FORMAT_EXTENSIBLE : u16 = 0xFFFE;
+
+fn read_header(p:&Path) -> Result<WavHeader> {
+  use std::io::{Read,Seek,SeekFrom};
+
+  let mut r = std::io::BufReader::new(std::fs::File::open(p)?);
+  let mut hdr = [0u8;12];
+  r.read_exact(&mut hdr)?;
+
+  //BW64 is the same thing as RF64 with a different name on it
+  let rf64 = match (&hdr[0..4],&hdr[8..12]) {
+    (b"RIFF",b"WAVE") => false,
+    (b"RF64" | b"BW64",b"WAVE") => true,
+    _ => Err(anyhow!("this isn't a wav file"))?
+  };
+
+  let mut spec = None;
+  let mut data64 = None;
+  let mut ch = [0u8;8];
+
+  while r.read_exact(&mut ch).is_ok() {
+    let size = u32::from_le_bytes([ch[4],ch[5],ch[6],ch[7]]) as u64;
+    let padded = size + (size & 1);
+
+    match &ch[0..4] {
+      //the riff size, data size and sample count, all 64 bits
+      b"ds64" => {
+        let mut buf = vec![0u8;size as usize];
+        r.read_exact(&mut buf)?;
+        data64 = buf.get(8..16).map(|b|u64::from_le_bytes(b.try_into().unwrap()));
+        r.seek(SeekFrom::Current((padded - size) as i64))?;
+      },
+
+      b"fmt " => {
+        let mut buf = vec![0u8;size as usize];
+        r.read_exact(&mut buf)?;
+        spec = Some(parse_fmt(&buf)?);
+        r.seek(SeekFrom::Current((padded - size) as i64))?;
+      },
+
+      b"data" => {
+        let (spec,block_align) = spec.ok_or(anyhow!("wav file has no fmt chunk before the data"))?;
+
+        let data_len = match (size,data64) {
+          (0xFFFFFFFF,Some(d)) if rf64 => d,
+          _ => size
+        };
+
+        return Ok(WavHeader{ spec, block_align, offset:r.stream_position()?, data_len, rf64 });
+      },
+
+      _ => {
+        r.seek(SeekFrom::Current(padded as i64))?;
+      }
+    }
+  }
+
+  Err(anyhow!("wav file has no data chunk"))
+}
+
+fn parse_fmt(b:&[u8]) -> Result<(hound::WavSpec,usize)> {
+  if b.len() < 16 {
+    return Err(anyhow!("wav fmt chunk is too short"));
+  }
+
+  let u16_at = |i:usize|u16::from_le_bytes([b[i],b[i+1]]);
+  let mut tag = u16_at(0);
+
+  //extensible files keep the real format at the front of the sub format guid
+  if tag == WAVE_FORMAT_EXTENSIBLE && b.len() >= 26 {
+    tag = u16_at(24);
+  }
+
+  let sample_format = if tag == WAVE_FORMAT_FLOAT {
+    hound::SampleFormat::Float
+  }
+  else {
+    hound::SampleFormat::Int
+  };
+
+  let spec = hound::WavSpec {
+    channels:u16_at(2),
+    sample_rate:u32::from_le_bytes([b[4],b[5],b[6],b[7]]),
+    bits_per_sample:u16_at(14),
+    sample_format
+  };
+
+  Ok((spec,u16_at(12) as usize))
+}
+
+//reads the samples straight out of the data chunk, anything
+//big enough gets mapped instead of read
+fn load_wav_direct(p:&Path,hdr:WavHeader) -> Result<(Snd,Format)> {
+  use std::io::{Read,Seek,SeekFrom};
 
-    _ => { Err(anyhow!("some kinda weird wav format here"))? }
+  let spec = hdr.spec;
+  let fmt = Format::from_spec(&spec)?;
+  let channels = spec.channels as usize;
+
+  //8 bit wavs are unsigned, nobody makes them this big anyway
+  if channels == 0 || spec.bits_per_sample <= 8 {
+    return Err(anyhow!("unsupported wav layout, {} channels of {} bits",channels,spec.bits_per_sample));
+  }
+
+  let width = hdr.block_align / channels;
+  let mut f = std::fs::File::open(p)?;
+
+  let seqs = if hdr.data_len > DISK_THRESHOLD {
+    let layout = blocks::DiskLayout{
+      offset:hdr.offset as usize,
+      frames:(hdr.data_len / hdr.block_align as u64) as usize,
+      channels,
+      width,
+      float:fmt.is_float(),
+      big_endian:false
+    };
+
+    blocks::Block::disk(&f,layout)?.into_iter().map(|b|b.into()).collect()
+  }
+  else {
+    f.seek(SeekFrom::Start(hdr.offset))?;
+    let mut bytes = vec![];
+    (&mut f).take(hdr.data_len).read_to_end(&mut bytes)?;
+
+    let frames = bytes.len() / hdr.block_align;
+    let samples : Vec<f32> = bytes[..frames*hdr.block_align].chunks_exact(width).map(|b|{
+      decode_raw(b,fmt,false)
+    }).collect();
+
+    deinterleave(&samples,channels)
   };
 
+  let snd = Snd::new(spec.sample_rate as usize,seqs).with_meta(read_meta(p));
+  Ok((snd,fmt))
+}
+
+fn deinterleave(samples:&[f32],channels:usize) -> Vec<Seq> {
   let mut seqs :Vec<Seq> = vec![];
     
   for chan in 0..channels {
@@ -44,24 +362,327 @@ pub fn load_wav<P:AsRef<Path>>(p:P) -> Result<Snd> {
     seqs.push(block.into());
   }
 
-  Ok(Snd::new(spec.sample_rate as usize,seqs))
+  seqs
 }
 
-pub fn save_wav<P:AsRef<Path>>(snd:&Snd,p:P) -> Result<()> {
+//ints get pushed to the top of an i32 so they all scale the same
+fn decode_raw(b:&[u8],fmt:Format,big_endian:bool) -> f32 {
+  match (fmt,big_endian) {
+    (Format::Float32,true) => f32::from_be_bytes([b[0],b[1],b[2],b[3]]),
+    (Format::Float32,false) => f32::from_le_bytes([b[0],b[1],b[2],b[3]]),
+    (_,true) => {
+      let v = b.iter().fold(0i32,|v,byte|(v << 8) | *byte as i32);
+      (v << (32 - 8*b.len())).to_sample::<f32>()
+    },
+    (_,false) => {
+      let v = b.iter().rev().fold(0i32,|v,byte|(v << 8) | *byte as i32);
+      (v << (32 - 8*b.len())).to_sample::<f32>()
+    }
+  }
+}
 
-  let spec = hound::WavSpec {
-    channels: snd.as_ref().len() as u16,
-    sample_rate: snd.sample_rate() as u32,
-    bits_per_sample: 16,
-    sample_format: hound::SampleFormat::Int,
-  };
+pub fn load_raw<P:AsRef<Path>>(p:P,layout:&RawLayout) -> Result<Snd> {
+  use std::io::{Read,Seek,SeekFrom};
 
-  let mut writer = hound::WavWriter::create(p, spec)?;
+  if layout.channels == 0 || layout.sample_rate == 0 {
+    return Err(anyhow!("raw files need at least one channel and a sample rate"));
+  }
+
+  let mut f = std::fs::File::open(p)?;
+  f.seek(SeekFrom::Start(layout.offset))?;
+
+  let mut bytes = vec![];
+  f.read_to_end(&mut bytes)?;
+
+  //a half written frame at the end just gets dropped
+  let width = (layout.format.bits() / 8) as usize;
+  let frame_bytes = width * layout.channels;
+  let frames = bytes.len() / frame_bytes;
+
+  let samples : Vec<f32> = bytes[..frames*frame_bytes].chunks_exact(width).map(|b|{
+    decode_raw(b,layout.format,layout.big_endian)
+  }).collect();
 
-  for smp in snd.interleaved_audio() {
-    writer.write_sample(smp.to_sample::<i16>())?;
+  Ok(Snd::new(layout.sample_rate,deinterleave(&samples,layout.channels)))
+}
+
+//only the format and endianness matter when saving, the rest comes from the snd
+pub fn save_raw<P:AsRef<Path>>(snd:&Snd,p:P,layout:&RawLayout,dither:Dither) -> Result<()> {
+  use std::io::Write;
+
+  swap_in(p.as_ref(),|tmp|{
+    let mut w = std::io::BufWriter::new(std::fs::File::create(tmp)?);
+    write_pcm(snd,&mut w,layout.format,layout.big_endian,dither)?;
+    w.flush()?;
+    Ok(())
+  })
+}
+
+//interleaved samples with no header, raw files and rf64 both want this
+fn write_pcm<W:std::io::Write>(snd:&Snd,w:&mut W,fmt:Format,big:bool,dither:Dither) -> Result<()> {
+  let channels = snd.channels();
+  let width = (fmt.bits() / 8) as usize;
+  let mut q = Quantizer::new(dither,fmt.bits(),channels);
+
+  for (i,smp) in snd.interleaved_audio().enumerate() {
+    match fmt {
+      Format::Float32 => {
+        w.write_all(&if big { smp.to_be_bytes() } else { smp.to_le_bytes() })?
+      },
+
+      Format::Int32 => {
+        let v = smp.to_sample::<i32>();
+        w.write_all(&if big { v.to_be_bytes() } else { v.to_le_bytes() })?
+      },
+
+      _ => {
+        let v = q.quantize(smp,i % channels);
+        if big {
+          w.write_all(&v.to_be_bytes()[4-width..])?
+        }
+        else {
+          w.write_all(&v.to_le_bytes()[..width])?
+        }
+      }
+    }
+  }
+
+  Ok(())
+}
+
+//riff sizes are 32 bits, so anything past 4GB has to go out as rf64.
+//everything is known up front so there's no going back to patch sizes
+fn save_rf64(snd:&Snd,p:&Path,fmt:Format,dither:Dither,meta:&Meta) -> Result<()> {
+  use std::io::Write;
+
+  let channels = snd.channels();
+  let frames = snd.len() as u64;
+  let width = (fmt.bits() / 8) as usize;
+  let block_align = (width * channels) as u16;
+  let data_len = frames * block_align as u64;
+  let pad = data_len & 1;
+  let chunks = meta::meta_chunks(meta);
+
+  let riff_len = 4 + (8 + 28) + (8 + 16) + (8 + data_len + pad) + chunks.len() as u64;
+  let tag = if fmt.is_float() { WAVE_FORMAT_FLOAT } else { 1 };
+  let rate = snd.sample_rate() as u32;
+
+  let mut w = std::io::BufWriter::new(std::fs::File::create(p)?);
+  w.write_all(b"RF64")?;
+  w.write_all(&0xFFFFFFFFu32.to_le_bytes())?;
+  w.write_all(b"WAVE")?;
+
+  w.write_all(b"ds64")?;
+  w.write_all(&28u32.to_le_bytes())?;
+  w.write_all(&riff_len.to_le_bytes())?;
+  w.write_all(&data_len.to_le_bytes())?;
+  w.write_all(&frames.to_le_bytes())?;
+  w.write_all(&0u32.to_le_bytes())?; //no table
+
+  w.write_all(b"fmt ")?;
+  w.write_all(&16u32.to_le_bytes())?;
+  w.write_all(&tag.to_le_bytes())?;
+  w.write_all(&(channels as u16).to_le_bytes())?;
+  w.write_all(&rate.to_le_bytes())?;
+  w.write_all(&(rate * block_align as u32).to_le_bytes())?;
+  w.write_all(&block_align.to_le_bytes())?;
+  w.write_all(&fmt.bits().to_le_bytes())?;
+
+  w.write_all(b"data")?;
+  w.write_all(&0xFFFFFFFFu32.to_le_bytes())?;
+  write_pcm(snd,&mut w,fmt,false,dither)?;
+
+  if pad == 1 {
+    w.write_all(&[0])?;
   }
 
+  w.write_all(&chunks)?;
+  w.flush()?;
   Ok(())
 }
 
+//dither only gets applied to the formats with fewer bits than a float,
+//32 bit ints and floats already have more resolution than we do
+pub fn save_wav<P:AsRef<Path>>(snd:&Snd,p:P,fmt:Format,dither:Dither,meta:&Meta) -> Result<()> {
+  let p = p.as_ref();
+  let channels = snd.channels();
+
+  //the headers are well under a kilobyte, the metadata could be anything
+  let data_len = (snd.len() * channels) as u64 * (fmt.bits() / 8) as u64;
+  let extra = 1024 + meta::meta_chunks(meta).len() as u64;
+  if data_len + extra > u32::MAX as u64 {
+    return save_rf64(snd,p,fmt,dither,meta);
+  }
+
+  let spec = fmt.wav_spec(channels as u16,snd.sample_rate() as u32);
+  let mut writer = hound::WavWriter::create(p, spec)?;
+
+  match fmt {
+    Format::Int16 => {
+      let mut q = Quantizer::new(dither,16,channels);
+      for (i,smp) in snd.interleaved_audio().enumerate() {
+        writer.write_sample(q.quantize(smp,i % channels) as i16)?;
+      }
+    },
+
+    Format::Int24 => {
+      //hound wants 24 bit samples packed into an i32
+      let mut q = Quantizer::new(dither,24,channels);
+      for (i,smp) in snd.interleaved_audio().enumerate() {
+        writer.write_sample(q.quantize(smp,i % channels))?;
+      }
+    },
+
+    Format::Int32 => {
+      for smp in snd.interleaved_audio() {
+        writer.write_sample(smp.to_sample::<i32>())?;
+      }
+    },
+
+    Format::Float32 => {
+      for smp in snd.interleaved_audio() {
+        writer.write_sample(smp)?;
+      }
+    }
+  }
+
+  writer.finalize()?;
+  meta::append_meta(p,meta)
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_decode_raw() {
+    assert_eq!(decode_raw(&[0x00,0x40],Format::Int16,false),0.5,"little endian 16 bit");
+    assert_eq!(decode_raw(&[0x40,0x00],Format::Int16,true),0.5,"big endian 16 bit");
+    assert_eq!(decode_raw(&[0x00,0x00,0xC0],Format::Int24,false),-0.5,"negative 24 bit");
+    assert_eq!(decode_raw(&0.25f32.to_be_bytes(),Format::Float32,true),0.25,"big endian floats");
+  }
+
+  #[test]
+  fn test_raw_round_trip() {
+    let path = std::env::temp_dir().join("ksnd_raw_round_trip.raw");
+    let smps : Vec<f32> = (0..100).map(|i|(i as f32 / 100.0) - 0.5).collect();
+    let seqs = vec![blocks::Block::data(smps.clone()).into(),blocks::Block::data(smps.clone()).into()];
+    let snd = Snd::new(8000,seqs);
+
+    for format in [Format::Int16,Format::Int24,Format::Int32,Format::Float32] {
+      for big_endian in [false,true] {
+        let layout = RawLayout{ sample_rate:8000, channels:2, format, big_endian, offset:0 };
+        save_raw(&snd,&path,&layout,Dither::Off).unwrap();
+        let back = load_raw(&path,&layout).unwrap();
+
+        assert_eq!(back.len(),100,"every frame should come back");
+        assert_eq!(back.channels(),2,"both channels should come back");
+
+        let out : Vec<f32> = back.interleaved_audio().collect();
+        for (a,b) in snd.interleaved_audio().zip(out) {
+          assert!((a - b).abs() < 1e-4,"samples should survive the trip");
+        }
+      }
+    }
+
+    //skipping a header should drop exactly that many bytes
+    let layout = RawLayout{ sample_rate:8000, channels:2, format:Format::Int16, big_endian:false, offset:4 };
+    save_raw(&snd,&path,&layout,Dither::Off).unwrap();
+    let back = load_raw(&path,&layout).unwrap();
+    assert_eq!(back.len(),99,"the offset should skip one stereo frame");
+
+    std::fs::remove_file(&path).ok();
+  }
+
+  #[test]
+  fn test_raw_over_disk_block() {
+    let path = std::env::temp_dir().join("ksnd_raw_over_disk.raw");
+    let smps : Vec<f32> = (0..3000).map(|i|(i as f32 / 3000.0) - 0.5).collect();
+    let snd = Snd::new(8000,vec![blocks::Block::data(smps.clone()).into()]);
+    let layout = RawLayout{ sample_rate:8000, channels:1, format:Format::Int16, big_endian:false, offset:0 };
+    save_raw(&snd,&path,&layout,Dither::Off).unwrap();
+
+    let disk = blocks::DiskLayout{ offset:0, frames:3000, channels:1, width:2, float:false, big_endian:false };
+    let file = std::fs::File::open(&path).unwrap();
+    let block = blocks::Block::disk(&file,disk).unwrap().remove(0);
+
+    //a tiny save over the same path shouldn't pull the map out from under the block
+    let short = Snd::new(8000,vec![blocks::Block::data(vec![0.0;10]).into()]);
+    save_raw(&short,&path,&layout,Dither::Off).unwrap();
+
+    assert_eq!(block.get_sample(2999).map(|s|(s - smps[2999]).abs() < 1e-4),Some(true),"the disk block should still read its old samples");
+    assert_eq!(load_raw(&path,&layout).unwrap().len(),10,"the new file should be in place");
+
+    std::fs::remove_file(&path).ok();
+  }
+
+  #[test]
+  fn test_rf64_round_trip() {
+    let path = std::env::temp_dir().join("ksnd_rf64_round_trip.wav");
+    let smps : Vec<f32> = (0..101).map(|i|(i as f32 / 101.0) - 0.5).collect();
+    let snd = Snd::new(48000,vec![blocks::Block::data(smps.clone()).into()]);
+
+    let mut meta = Meta::default();
+    meta.set_info("INAM","long").unwrap();
+
+    for fmt in [Format::Int24,Format::Float32] {
+      save_rf64(&snd,&path,fmt,Dither::Off,&meta).unwrap();
+      let (back,back_fmt) = load_wav(&path).unwrap();
+
+      assert_eq!(back_fmt,fmt,"rf64 files should remember their format");
+      assert_eq!(back.len(),101,"every frame should come back out of rf64");
+      assert_eq!(back.meta(),&meta,"chunks after the data should still be found");
+
+      for (a,b) in snd.interleaved_audio().zip(back.interleaved_audio()) {
+        assert!((a - b).abs() < 1e-6,"rf64 samples should survive the trip");
+      }
+    }
+
+    std::fs::remove_file(&path).ok();
+  }
+
+  #[test]
+  fn test_odd_bit_depths() {
+    let path = std::env::temp_dir().join("ksnd_odd_bits.wav");
+
+    let spec = hound::WavSpec{ channels:1, sample_rate:44100, bits_per_sample:8, sample_format:hound::SampleFormat::Int };
+    let mut w = hound::WavWriter::create(&path,spec).unwrap();
+    w.write_sample(64i8).unwrap();
+    w.write_sample(-64i8).unwrap();
+    w.finalize().unwrap();
+
+    let (back,_) = load_wav(&path).unwrap();
+    assert_eq!(back.interleaved_audio().collect::<Vec<_>>(),vec![0.5,-0.5],"8 bit samples should be full scale");
+
+    //20 valid bits in a 24 bit container, the way extensible files do it
+    let pcm = [1,0,0,0,0,0,0x10,0,0x80,0,0,0xAA,0,0x38,0x9B,0x71];
+    let mut fmt = vec![];
+    fmt.extend(0xFFFEu16.to_le_bytes());
+    fmt.extend(1u16.to_le_bytes());
+    fmt.extend(44100u32.to_le_bytes());
+    fmt.extend((44100u32*3).to_le_bytes());
+    fmt.extend(3u16.to_le_bytes());
+    fmt.extend(24u16.to_le_bytes());
+    fmt.extend(22u16.to_le_bytes());
+    fmt.extend(20u16.to_le_bytes());
+    fmt.extend(4u32.to_le_bytes());
+    fmt.extend(pcm);
+
+    let data = [0x00,0x00,0x40,0x00,0x00,0xE0];
+    let mut file = b"RIFF".to_vec();
+    file.extend(((4 + 8 + fmt.len() + 8 + data.len()) as u32).to_le_bytes());
+    file.extend(b"WAVEfmt ");
+    file.extend((fmt.len() as u32).to_le_bytes());
+    file.extend(fmt);
+    file.extend(b"data");
+    file.extend((data.len() as u32).to_le_bytes());
+    file.extend(data);
+    std::fs::write(&path,&file).unwrap();
+
+    let (back,back_fmt) = load_wav(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert_eq!(back_fmt,Format::Int24,"20 bits gets saved back as 24");
+    assert_eq!(back.interleaved_audio().collect::<Vec<_>>(),vec![0.5,-0.25],"20 bit samples should be full scale");
+  }
+}
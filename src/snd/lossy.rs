@@ -0,0 +1,95 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::{Snd,Format};
+
+//mp3 and ogg vorbis go through symphonia, it's a big dependency
+//so it lives behind the "lossy" feature
+#[cfg(feature="lossy")]
+pub fn load_lossy<P:AsRef<Path>>(p:P) -> Result<(Snd,Format)> {
+  use anyhow::anyhow;
+  use symphonia::core::{
+    audio::Signal,
+    codecs::DecoderOptions,
+    errors::Error,
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint
+  };
+  use crate::blocks::{Block,BlockSequence as Seq};
+
+  let p = p.as_ref();
+  let file = std::fs::File::open(p)?;
+  let mss = MediaSourceStream::new(Box::new(file),Default::default());
+
+  let mut hint = Hint::new();
+  if let Some(ext) = p.extension().and_then(|e|e.to_str()) {
+    hint.with_extension(ext);
+  }
+
+  //gapless trims the encoder delay and padding off mp3s
+  let fmt_opts = FormatOptions{ enable_gapless:true, ..Default::default() };
+  let probed = symphonia::default::get_probe().format(&hint,mss,&fmt_opts,&MetadataOptions::default())?;
+  let mut reader = probed.format;
+
+  let track = reader.default_track().ok_or(anyhow!("there's no audio in this file"))?;
+  let track_id = track.id;
+  let mut sample_rate = track.codec_params.sample_rate;
+  let mut decoder = symphonia::default::get_codecs().make(&track.codec_params,&DecoderOptions::default())?;
+
+  let mut chans : Vec<Vec<f32>> = vec![];
+
+  loop {
+    let packet = match reader.next_packet() {
+      Ok(packet) => packet,
+      Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+      Err(e) => Err(e)?
+    };
+
+    if packet.track_id() != track_id {
+      continue;
+    }
+
+    let decoded = match decoder.decode(&packet) {
+      Ok(d) => d,
+      //a corrupt frame isn't worth giving up the whole file over
+      Err(Error::DecodeError(_)) => continue,
+      Err(e) => Err(e)?
+    };
+
+    let spec = *decoded.spec();
+    sample_rate = sample_rate.or(Some(spec.rate));
+
+    if chans.is_empty() {
+      chans = vec![vec![];spec.channels.count()];
+    }
+
+    //chained oggs and some mp3s can switch channel counts partway through
+    if spec.channels.count() != chans.len() {
+      return Err(anyhow!("the number of channels changes partway through this file"));
+    }
+
+    let mut out = decoded.make_equivalent::<f32>();
+    decoded.convert(&mut out);
+
+    for (c,chan) in chans.iter_mut().enumerate() {
+      chan.extend_from_slice(out.chan(c));
+    }
+  }
+
+  let sample_rate = sample_rate.ok_or(anyhow!("couldn't figure out the sample rate"))?;
+  let seqs = chans.into_iter().map(|c|{
+    let s : Seq = Block::data(c).into();
+    s
+  });
+
+  //there's no bit depth to remember here, so saving defaults to plain old 16 bit
+  Ok((Snd::from_iter(sample_rate as usize,seqs),Format::Int16))
+}
+
+#[cfg(not(feature="lossy"))]
+pub fn load_lossy<P:AsRef<Path>>(_p:P) -> Result<(Snd,Format)> {
+  Err(anyhow::anyhow!("mp3 and ogg support isn't built in, rebuild with --features lossy"))
+}
@@ -0,0 +1,554 @@
+use std::path::Path;
+use std::io::{Read,Seek,SeekFrom,Write};
+
+use anyhow::Result;
+
+//a marker dropped somewhere in the file, the label comes out of
+//the LIST/adtl chunk if there is one
+#[derive(Debug,Clone,PartialEq)]
+pub struct Cue {
+  pub id:u32,
+  pub pos:usize,
+  pub label:Option<String>
+}
+
+//sampler loop points, end is inclusive like the smpl chunk has it
+#[derive(Debug,Clone,PartialEq)]
+pub struct Loop {
+  pub cue_id:u32,
+  pub kind:u32,
+  pub start:usize,
+  pub end:usize,
+  pub fraction:u32,
+  pub count:u32
+}
+
+#[derive(Debug,Clone,PartialEq,Default)]
+pub struct Sampler {
+  pub manufacturer:u32,
+  pub product:u32,
+  pub period:u32,
+  pub unity_note:u32,
+  pub pitch_fraction:u32,
+  pub smpte_format:u32,
+  pub smpte_offset:u32,
+  pub loops:Vec<Loop>,
+  pub data:Vec<u8>
+}
+
+//broadcast wave stuff, most of it is fixed width text so it gets
+//kept as bytes and only the bits we care about get pulled out
+#[derive(Debug,Clone,PartialEq)]
+pub struct Bext {
+  raw:Vec<u8>
+}
+
+const BEXT_MIN : usize = 602;
+
+impl Bext {
+  pub fn description(&self) -> String {
+    fixed_str(&self.raw[0..256])
+  }
+
+  pub fn set_description(&mut self,s:&str) {
+    let b = s.as_bytes();
+    let n = b.len().min(256);
+    self.raw[0..256].fill(0);
+    self.raw[0..n].copy_from_slice(&b[..n]);
+  }
+
+  pub fn originator(&self) -> String {
+    fixed_str(&self.raw[256..288])
+  }
+
+  //the sample count since midnight of the first sample in the file
+  pub fn time_reference(&self) -> u64 {
+    let mut b = [0u8;8];
+    b.copy_from_slice(&self.raw[338..346]);
+    u64::from_le_bytes(b)
+  }
+
+  pub fn set_time_reference(&mut self,t:u64) {
+    self.raw[338..346].copy_from_slice(&t.to_le_bytes());
+  }
+}
+
+impl Default for Bext {
+  fn default() -> Self {
+    let mut raw = vec![0u8;BEXT_MIN];
+    //version 1, so nobody goes looking for loudness values
+    raw[346] = 1;
+    Self{ raw }
+  }
+}
+
+fn fixed_str(b:&[u8]) -> String {
+  let end = b.iter().position(|c|*c == 0).unwrap_or(b.len());
+  String::from_utf8_lossy(&b[..end]).trim_end().to_string()
+}
+
+//everything in a wav besides the samples that's worth keeping
+#[derive(Debug,Clone,PartialEq,Default)]
+pub struct Meta {
+  pub cues:Vec<Cue>,
+  pub sampler:Option<Sampler>,
+  pub info:Vec<([u8;4],String)>,
+  pub bext:Option<Bext>
+}
+
+impl Meta {
+  pub fn is_empty(&self) -> bool {
+    self.cues.is_empty() && self.sampler.is_none() && self.info.is_empty() && self.bext.is_none()
+  }
+
+  fn positions(&mut self) -> impl Iterator<Item=&mut usize> {
+    let cues = self.cues.iter_mut().map(|c|&mut c.pos);
+    let loops = self.sampler.iter_mut().flat_map(|s|s.loops.iter_mut()).flat_map(|l|[&mut l.start,&mut l.end]);
+    cues.chain(loops)
+  }
+
+  //cues inside the removed bit go with it, loop points get pulled
+  //in to the edge and loops that end up empty get dropped
+  pub fn delete(&mut self,s:usize,e:usize) {
+    if e <= s {
+      return;
+    }
+
+    self.cues.retain(|c|c.pos < s || c.pos >= e);
+
+    for c in self.cues.iter_mut() {
+      if c.pos >= e {
+        c.pos -= e - s;
+      }
+    }
+
+    //loop ends are inclusive, so an end that got cut off has to land
+    //on the last sample before the cut, not the first one after it
+    if let Some(smpl) = &mut self.sampler {
+      smpl.loops.retain_mut(|l|{
+        if l.start >= e {
+          l.start -= e - s;
+        }
+        else if l.start > s {
+          l.start = s;
+        }
+
+        if l.end >= e {
+          l.end -= e - s;
+        }
+        else if l.end >= s {
+          if s == 0 {
+            return false;
+          }
+          l.end = s - 1;
+        }
+
+        l.end > l.start
+      });
+    }
+
+    //the first sample is later in the day than it used to be
+    if s == 0 {
+      if let Some(bext) = &mut self.bext {
+        bext.set_time_reference(bext.time_reference() + e as u64);
+      }
+    }
+  }
+
+  pub fn insert(&mut self,pt:usize,len:usize) {
+    for p in self.positions() {
+      if *p >= pt {
+        *p += len;
+      }
+    }
+  }
+
+  //stuff inside the replaced region gets squished or stretched along with it
+  pub fn replace(&mut self,s:usize,e:usize,new_len:usize) {
+    let old_len = e.saturating_sub(s);
+
+    for p in self.positions() {
+      if *p >= e {
+        *p = *p - old_len + new_len;
+      }
+      else if *p >= s {
+        *p = s + ((*p - s) as f64 * new_len as f64 / old_len as f64) as usize;
+      }
+    }
+  }
+
+  pub fn crop(&mut self,s:usize,e:usize,len:usize) {
+    self.delete(e,len);
+    self.delete(0,s);
+  }
+
+  pub fn rescale(&mut self,ratio:f64) {
+    for p in self.positions() {
+      *p = (*p as f64 * ratio).round() as usize;
+    }
+
+    if let Some(bext) = &mut self.bext {
+      bext.set_time_reference((bext.time_reference() as f64 * ratio).round() as u64);
+    }
+  }
+
+  pub fn next_cue_id(&self) -> u32 {
+    self.cues.iter().map(|c|c.id + 1).max().unwrap_or(1)
+  }
+
+  pub fn info(&self,key:&str) -> Option<&str> {
+    let key = info_key(key)?;
+    self.info.iter().find(|(k,_)|*k == key).map(|(_,v)|v.as_str())
+  }
+
+  //an empty value removes the tag
+  pub fn set_info(&mut self,key:&str,val:&str) -> Result<()> {
+    let key = info_key(key).ok_or(anyhow::anyhow!("info keys are 4 characters, like INAM or ICMT"))?;
+    self.info.retain(|(k,_)|*k != key);
+
+    if !val.is_empty() {
+      self.info.push((key,val.to_string()));
+    }
+
+    Ok(())
+  }
+}
+
+fn info_key(key:&str) -> Option<[u8;4]> {
+  key.to_uppercase().as_bytes().try_into().ok()
+}
+
+fn le32(b:&[u8]) -> u32 {
+  u32::from_le_bytes([b[0],b[1],b[2],b[3]])
+}
+
+fn parse_cues(b:&[u8],meta:&mut Meta) {
+  let n = b.get(0..4).map(le32).unwrap_or(0) as usize;
+
+  for c in b.get(4..).unwrap_or(&[]).chunks_exact(24).take(n) {
+    meta.cues.push(Cue{ id:le32(&c[0..4]), pos:le32(&c[20..24]) as usize, label:None });
+  }
+}
+
+fn parse_smpl(b:&[u8]) -> Option<Sampler> {
+  if b.len() < 36 {
+    return None;
+  }
+
+  let w = |i:usize|le32(&b[i*4..i*4+4]);
+  let n = w(7) as usize;
+  let data_len = w(8) as usize;
+
+  let loops = b[36..].chunks_exact(24).take(n).map(|l|Loop{
+    cue_id:le32(&l[0..4]),
+    kind:le32(&l[4..8]),
+    start:le32(&l[8..12]) as usize,
+    end:le32(&l[12..16]) as usize,
+    fraction:le32(&l[16..20]),
+    count:le32(&l[20..24])
+  }).collect();
+
+  let data_start = (36 + n*24).min(b.len());
+  let data = b[data_start..(data_start + data_len).min(b.len())].to_vec();
+
+  Some(Sampler{
+    manufacturer:w(0),
+    product:w(1),
+    period:w(2),
+    unity_note:w(3),
+    pitch_fraction:w(4),
+    smpte_format:w(5),
+    smpte_offset:w(6),
+    loops,
+    data
+  })
+}
+
+//LIST chunks hold more little chunks, INFO has text tags
+//and adtl has the cue labels
+fn parse_list(b:&[u8],meta:&mut Meta,labels:&mut Vec<(u32,String)>) {
+  if b.len() < 4 {
+    return;
+  }
+
+  let kind = &b[0..4];
+  let mut i = 4;
+
+  while i + 8 <= b.len() {
+    let id : [u8;4] = b[i..i+4].try_into().unwrap();
+    let size = le32(&b[i+4..i+8]) as usize;
+    let body = &b[i+8..(i+8+size).min(b.len())];
+
+    match (kind,&id) {
+      (b"INFO",_) => meta.info.push((id,fixed_str(body))),
+      (b"adtl",b"labl") if body.len() >= 4 => labels.push((le32(body),fixed_str(&body[4..]))),
+      _ => ()
+    }
+
+    i += 8 + size + (size & 1);
+  }
+}
+
+//hound doesn't care about any of this, so we walk the chunks ourselves
+pub fn read_meta<P:AsRef<Path>>(p:P) -> Result<Meta> {
+  let f = std::fs::File::open(p)?;
+  let file_len = f.metadata()?.len();
+  let mut r = std::io::BufReader::new(f);
+  let mut meta = Meta::default();
+  let mut labels = vec![];
+
+  let mut hdr = [0u8;12];
+  r.read_exact(&mut hdr)?;
+
+  //rf64 files keep the real size of the data chunk in ds64
+  let mut data64 = None;
+
+  let mut ch = [0u8;8];
+  while r.read_exact(&mut ch).is_ok() {
+    let mut size = le32(&ch[4..8]) as u64;
+
+    if &ch[0..4] == b"data" && size == 0xFFFFFFFF {
+      size = data64.unwrap_or(size);
+    }
+
+    let padded = size + (size & 1);
+
+    if &ch[0..4] == b"ds64" && size >= 16 {
+      let mut ds64 = [0u8;16];
+      r.read_exact(&mut ds64)?;
+      data64 = Some(u64::from_le_bytes(ds64[8..16].try_into().unwrap()));
+      r.seek(SeekFrom::Current((padded - 16) as i64))?;
+      continue;
+    }
+
+    //the data chunk is the big one, no reason to read it
+    if !matches!(&ch[0..4],b"cue " | b"smpl" | b"LIST" | b"bext") {
+      r.seek(SeekFrom::Current(padded as i64))?;
+      continue;
+    }
+
+    //a corrupt size shouldn't get to ask for gigs, what's left of the file is plenty
+    let left = file_len.saturating_sub(r.stream_position()?);
+    let size = size.min(left);
+
+    let mut buf = vec![0u8;size as usize];
+    r.read_exact(&mut buf)?;
+    r.seek(SeekFrom::Current((padded - size) as i64))?;
+
+    match &ch[0..4] {
+      b"cue " => parse_cues(&buf,&mut meta),
+      b"smpl" => meta.sampler = parse_smpl(&buf),
+      b"LIST" => parse_list(&buf,&mut meta,&mut labels),
+      _ => {
+        buf.resize(buf.len().max(BEXT_MIN),0);
+        meta.bext = Some(Bext{ raw:buf });
+      }
+    }
+  }
+
+  for (id,label) in labels {
+    if let Some(c) = meta.cues.iter_mut().find(|c|c.id == id) {
+      c.label = Some(label);
+    }
+  }
+
+  Ok(meta)
+}
+
+fn chunk(id:&[u8;4],body:&[u8]) -> Vec<u8> {
+  let mut out = id.to_vec();
+  out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+  out.extend_from_slice(body);
+
+  if body.len() & 1 == 1 {
+    out.push(0);
+  }
+
+  out
+}
+
+fn text_chunk(id:&[u8;4],prefix:&[u8],s:&str) -> Vec<u8> {
+  let mut body = prefix.to_vec();
+  body.extend_from_slice(s.as_bytes());
+  body.push(0);
+  chunk(id,&body)
+}
+
+pub fn meta_chunks(meta:&Meta) -> Vec<u8> {
+  let mut out = vec![];
+
+  if let Some(bext) = &meta.bext {
+    out.extend(chunk(b"bext",&bext.raw));
+  }
+
+  if !meta.cues.is_empty() {
+    let mut body = (meta.cues.len() as u32).to_le_bytes().to_vec();
+
+    for c in meta.cues.iter() {
+      let pos = (c.pos as u32).to_le_bytes();
+      body.extend_from_slice(&c.id.to_le_bytes());
+      body.extend_from_slice(&pos);
+      body.extend_from_slice(b"data");
+      body.extend_from_slice(&[0;8]);
+      body.extend_from_slice(&pos);
+    }
+
+    out.extend(chunk(b"cue ",&body));
+
+    let labels : Vec<u8> = meta.cues.iter().filter_map(|c|{
+      c.label.as_ref().map(|l|text_chunk(b"labl",&c.id.to_le_bytes(),l))
+    }).flatten().collect();
+
+    if !labels.is_empty() {
+      out.extend(chunk(b"LIST",&[b"adtl".as_slice(),&labels].concat()));
+    }
+  }
+
+  if let Some(smpl) = &meta.sampler {
+    let mut body = vec![];
+    let head = [
+      smpl.manufacturer,smpl.product,smpl.period,smpl.unity_note,smpl.pitch_fraction,
+      smpl.smpte_format,smpl.smpte_offset,smpl.loops.len() as u32,smpl.data.len() as u32
+    ];
+
+    for w in head {
+      body.extend_from_slice(&w.to_le_bytes());
+    }
+
+    for l in smpl.loops.iter() {
+      for w in [l.cue_id,l.kind,l.start as u32,l.end as u32,l.fraction,l.count] {
+        body.extend_from_slice(&w.to_le_bytes());
+      }
+    }
+
+    body.extend_from_slice(&smpl.data);
+    out.extend(chunk(b"smpl",&body));
+  }
+
+  if !meta.info.is_empty() {
+    let tags : Vec<u8> = meta.info.iter().flat_map(|(k,v)|text_chunk(k,&[],v)).collect();
+    out.extend(chunk(b"LIST",&[b"INFO".as_slice(),&tags].concat()));
+  }
+
+  out
+}
+
+//hound has already written the file, so the chunks go on the end
+//and the riff size gets fixed up to match
+pub fn append_meta<P:AsRef<Path>>(p:P,meta:&Meta) -> Result<()> {
+  if meta.is_empty() {
+    return Ok(());
+  }
+
+  let mut f = std::fs::OpenOptions::new().read(true).write(true).open(p)?;
+  let mut len = f.seek(SeekFrom::End(0))?;
+
+  //hound doesn't pad an odd sized data chunk
+  if len & 1 == 1 {
+    f.write_all(&[0])?;
+    len += 1;
+  }
+
+  let chunks = meta_chunks(meta);
+  f.write_all(&chunks)?;
+
+  let riff_len = (len + chunks.len() as u64 - 8) as u32;
+  f.seek(SeekFrom::Start(4))?;
+  f.write_all(&riff_len.to_le_bytes())?;
+  f.flush()?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn test_meta() -> Meta {
+    let mut meta = Meta::default();
+    meta.cues.push(Cue{ id:1, pos:100, label:Some("one".into()) });
+    meta.cues.push(Cue{ id:2, pos:500, label:None });
+    meta.sampler = Some(Sampler{
+      loops:vec![Loop{ cue_id:0, kind:0, start:200, end:400, fraction:0, count:0 }],
+      ..Default::default()
+    });
+    meta
+  }
+
+  #[test]
+  fn test_delete_shifts() {
+    let mut meta = test_meta();
+    meta.delete(50,150);
+    assert_eq!(meta.cues.len(),1,"the cue inside the deletion should go");
+    assert_eq!(meta.cues[0].pos,400,"cues after the deletion should move back");
+
+    let l = &meta.sampler.as_ref().unwrap().loops[0];
+    assert_eq!((l.start,l.end),(100,300),"loops should move back too");
+
+    let mut meta = test_meta();
+    meta.delete(300,1000);
+    let l = &meta.sampler.as_ref().unwrap().loops[0];
+    assert_eq!((l.start,l.end),(200,299),"loop ends inside a deletion get pulled in");
+
+    let mut meta = test_meta();
+    meta.delete(400,500);
+    let l = &meta.sampler.as_ref().unwrap().loops[0];
+    assert_eq!((l.start,l.end),(200,399),"cutting just the last loop sample shouldn't pull in the next one");
+
+    meta.delete(0,1000);
+    assert!(meta.sampler.unwrap().loops.is_empty(),"empty loops should get dropped");
+  }
+
+  #[test]
+  fn test_insert_and_replace() {
+    let mut meta = test_meta();
+    meta.insert(100,50);
+    assert_eq!(meta.cues[0].pos,150,"a cue at the insert point moves after it");
+    assert_eq!(meta.cues[1].pos,550,"later cues move too");
+
+    let mut meta = test_meta();
+    meta.replace(0,400,200);
+    assert_eq!(meta.cues[0].pos,50,"cues inside a replacement get scaled");
+    assert_eq!(meta.cues[1].pos,300,"cues after a replacement get shifted");
+  }
+
+  #[test]
+  fn test_chunk_round_trip() {
+    let mut meta = test_meta();
+    meta.set_info("inam","a title").unwrap();
+    let mut bext = Bext::default();
+    bext.set_description("hello");
+    bext.set_time_reference(12345);
+    meta.bext = Some(bext);
+
+    let path = std::env::temp_dir().join("ksnd_meta_round_trip.wav");
+    let mut file = b"RIFF\0\0\0\0WAVE".to_vec();
+    file.extend(meta_chunks(&meta));
+    std::fs::write(&path,&file).unwrap();
+
+    let back = read_meta(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(back,meta,"metadata should survive being written and read");
+    assert_eq!(back.info("INAM"),Some("a title"),"info tags are looked up by key");
+    assert_eq!(back.bext.unwrap().description(),"hello","descriptions should come back");
+  }
+
+  #[test]
+  fn test_broken_chunks() {
+    let mut cues = Meta::default();
+    parse_cues(&[1,0],&mut cues);
+    assert!(cues.cues.is_empty(),"a cue chunk too short for its count is just empty");
+
+    //a cue chunk that claims to be way bigger than the file
+    let path = std::env::temp_dir().join("ksnd_meta_broken.wav");
+    let mut file = b"RIFF\0\0\0\0WAVEcue ".to_vec();
+    file.extend(0xFFFFFFF0u32.to_le_bytes());
+    file.extend([1,0,0,0]);
+    std::fs::write(&path,&file).unwrap();
+
+    let back = read_meta(&path);
+    std::fs::remove_file(&path).ok();
+    assert_eq!(back.unwrap(),Meta::default(),"the bad size should get cut down to what's there");
+  }
+}
@@ -1,6 +1,23 @@
 mod types;
 pub use types::Snd;
 
+mod format;
+pub use format::Format;
+
+mod meta;
+pub use meta::{Meta,Cue,Loop,Sampler,Bext};
+
+mod flac;
+mod aiff;
+mod lossy;
+mod opus;
+
 mod fs;
-pub use fs::load_wav;
-pub use fs::save_wav;
+pub use fs::SaveOpts;
+pub use fs::load;
+pub use fs::save;
+pub use fs::EXTENSIONS;
+pub use fs::load_extensions;
+pub use fs::RawLayout;
+pub use fs::load_raw;
+pub use fs::save_raw;
@@ -0,0 +1,120 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::{Snd,Format};
+
+//opus needs libopus, which is a native library, so it gets its own
+//feature rather than riding along with the rest of the lossy stuff
+#[cfg(feature="opus")]
+pub fn load_opus<P:AsRef<Path>>(p:P) -> Result<(Snd,Format)> {
+  use anyhow::anyhow;
+  use audiopus::{
+    coder::Decoder,
+    packet::Packet,
+    Channels,
+    MutSignals,
+    SampleRate
+  };
+  use crate::blocks::{Block,BlockSequence as Seq};
+
+  let f = std::io::BufReader::new(std::fs::File::open(p)?);
+  let mut r = ogg::PacketReader::new(f);
+
+  let head = r.read_packet()?.ok_or(anyhow!("this ogg file is empty"))?;
+  let head = Head::parse(&head.data)?;
+
+  //the second packet is the comment header, we don't do anything with it
+  r.read_packet()?;
+
+  let layout = match head.channels {
+    1 => Channels::Mono,
+    2 => Channels::Stereo,
+    c => Err(anyhow!("only mono and stereo opus files are supported, this has {} channels",c))?
+  };
+
+  let channels = head.channels;
+  let mut decoder = Decoder::new(SampleRate::Hz48000,layout)?;
+
+  //120ms at 48k is the longest an opus packet can be
+  let mut buf = vec![0.0f32;5760 * channels];
+  let mut chans : Vec<Vec<f32>> = vec![vec![];channels];
+  let mut end = None;
+
+  while let Some(pkt) = r.read_packet()? {
+    let input = Packet::try_from(&pkt.data[..])?;
+    let output = MutSignals::try_from(&mut buf[..])?;
+    let n = decoder.decode_float(Some(input),output,false)?;
+
+    for frame in buf[..n*channels].chunks_exact(channels) {
+      for (c,smp) in frame.iter().enumerate() {
+        chans[c].push(smp * head.gain);
+      }
+    }
+
+    end = Some(pkt.absgp_page());
+  }
+
+  let seqs = chans.into_iter().map(|c|{
+    let s : Seq = Block::data(trim(c,head.pre_skip,end)).into();
+    s
+  });
+
+  //opus always decodes at 48k, whatever the original rate was
+  Ok((Snd::from_iter(48000,seqs),Format::Int16))
+}
+
+//the pre skip is encoder junk at the front, and the last granule
+//position tells us where the real audio stops. a cut off file can
+//have less audio than the pre skip, that's just an empty channel
+#[cfg(any(feature="opus",test))]
+fn trim(mut c:Vec<f32>,pre_skip:usize,end:Option<u64>) -> Vec<f32> {
+  let skip = pre_skip.min(c.len());
+  let stop = end.map(|e|e as usize).unwrap_or(c.len()).clamp(skip,c.len());
+  c.truncate(stop);
+  c.drain(..skip);
+  c
+}
+
+#[cfg(not(feature="opus"))]
+pub fn load_opus<P:AsRef<Path>>(_p:P) -> Result<(Snd,Format)> {
+  Err(anyhow::anyhow!("opus support isn't built in, rebuild with --features opus"))
+}
+
+//the "OpusHead" packet at the start of every ogg opus stream
+#[cfg(feature="opus")]
+struct Head {
+  channels:usize,
+  pre_skip:usize,
+  gain:f32
+}
+
+#[cfg(feature="opus")]
+impl Head {
+  fn parse(b:&[u8]) -> Result<Self> {
+    if b.len() < 19 || &b[0..8] != b"OpusHead" {
+      return Err(anyhow::anyhow!("this ogg file isn't opus"));
+    }
+
+    //the gain is in 1/256ths of a dB
+    let gain_db = i16::from_le_bytes([b[16],b[17]]) as f32 / 256.0;
+
+    Ok(Self {
+      channels:b[9] as usize,
+      pre_skip:u16::from_le_bytes([b[10],b[11]]) as usize,
+      gain:10f32.powf(gain_db/20.0)
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_short_stream() {
+    assert_eq!(trim(vec![0.5;100],312,Some(50)),Vec::<f32>::new(),"less audio than the pre skip leaves nothing");
+    assert_eq!(trim(vec![],312,None),Vec::<f32>::new(),"an empty stream stays empty");
+    assert_eq!(trim(vec![0.5;1000],312,Some(900)).len(),588,"the skip and the end both come off");
+  }
+}
@@ -1,26 +1,41 @@
 use crate::blocks;
 use blocks::BlockSequence as Seq;
+use super::Meta;
 
+//meta is whatever came in with the file, once it's being
+//edited the ctx keeps the up to date copy
 pub struct Snd {
   sample_rate:usize,
-  channels:Vec<Seq>
+  channels:Vec<Seq>,
+  meta:Meta
 }
 
 impl Snd {
   pub fn new(sr:usize,channels:Vec<Seq>) -> Self {
     Self {
       sample_rate:sr,
-      channels
+      channels,
+      meta:Meta::default()
     }
   }
 
   pub fn from_iter<T:IntoIterator<Item=Seq>>(sr:usize,i:T) -> Self {
     Self {
       sample_rate:sr,
-      channels:i.into_iter().collect()
+      channels:i.into_iter().collect(),
+      meta:Meta::default()
     }
   }
 
+  pub fn with_meta(mut self,meta:Meta) -> Self {
+    self.meta = meta;
+    self
+  }
+
+  pub fn meta(&self) -> &Meta {
+    &self.meta
+  }
+
   //simple data getters
   pub fn len(&self) -> usize {
     self.channels.iter().fold(0,|mx,c|mx.max(c.len()))
@@ -83,7 +98,7 @@ impl<'a> Iterator for InterLeaved<'a> {
       return None;
     }
     
-    let out = self.snd[self.chan].get_sample(&self.sample).copied().unwrap_or(0.0);
+    let out = self.snd[self.chan].get_sample(&self.sample).unwrap_or(0.0);
 
     self.chan += 1;
     
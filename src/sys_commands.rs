@@ -2,7 +2,7 @@ use std::sync::Arc;
 use iced::window::Id;
 
 use crate::{
-  snd::Snd,
+  snd::{Snd,Format},
   audio_sys::{ConfAction,StreamReq},
   editor_window::Action as EdAction,
 
@@ -12,7 +12,7 @@ pub enum SysCommand {
   Play(Id,Arc<Snd>,f64,f64,f64,bool),
   Stop(Id),
   SetupAudio(StreamReq),
-  OpenEditor(Arc<Snd>,Option<String>),
+  OpenEditor(Arc<Snd>,Option<String>,Format),
   OpenAudioConfig,
 }
 
@@ -22,7 +22,7 @@ impl SysCommand {
       EdAction::None => None,
       EdAction::Play(snd,s,e,pt,lp) => Some(Self::Play(id,snd,s,e,pt,lp)),
       EdAction::Stop => Some(Self::Stop(id)),
-      EdAction::OpenNew(snd,strn) => Some(Self::OpenEditor(snd,strn)),
+      EdAction::OpenNew(snd,strn,fmt) => Some(Self::OpenEditor(snd,strn,fmt)),
       EdAction::ConfigAudio => Some(Self::OpenAudioConfig)
     }
   }
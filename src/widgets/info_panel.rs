@@ -22,7 +22,12 @@ fn format_region(title:&str,r:crate::util::Region,sample_rate:f64) -> String {
 pub fn view<'b>(ed:&Editor) -> Element<'b,()> {
   let ctx = ed.ctx();
   let sr = format!("sample rate: {}",ctx.snd.sample_rate());
+  let fmt = format!("format: {}",ed.save_opts().format);
   let lpm = format!("loop: {}",if ctx.loop_mode { "on" } else {"off"});
+  let loops = ctx.meta.sampler.as_ref().map(|s|s.loops.len()).unwrap_or(0);
+  let markers = format!("cues: {} loops: {}",ctx.meta.cues.len(),loops);
+  let mb = ed.history_bytes() as f64 / (1024.0 * 1024.0);
+  let history = format!("last: {} ({:.1} MB of undo)",ed.history_label(),mb);
 
   let fsr = ctx.snd.sample_rate() as f64;
 
@@ -39,8 +44,8 @@ pub fn view<'b>(ed:&Editor) -> Element<'b,()> {
   .unwrap_or("<No Cursor>".to_string());
 
   row![
-    column![Text::new(path),Text::new(sr),Text::new(view_region)].spacing(5),
-    column![Text::new(lpm),Text::new(sel_region),Text::new(cursor)].spacing(5),
+    column![Text::new(path),Text::new(sr),Text::new(fmt),Text::new(view_region),Text::new(history)].spacing(5),
+    column![Text::new(lpm),Text::new(sel_region),Text::new(cursor),Text::new(markers)].spacing(5),
   ]
   .spacing(10)
   .into()
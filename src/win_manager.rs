@@ -11,7 +11,7 @@ use iced::{
 };
 
 use crate::{
-  snd::Snd,
+  snd::{Snd,Format},
   
   editor_window::{
     Win as EdWin,
@@ -77,8 +77,8 @@ impl State {
     }
   }
 
-  pub fn open_editor(&mut self,id:window::Id,snd:Arc<Snd>,title:Option<String>) -> Result<()> {
-    let win = EdWin::new(snd,title)?;
+  pub fn open_editor(&mut self,id:window::Id,snd:Arc<Snd>,title:Option<String>,fmt:Format) -> Result<()> {
+    let win = EdWin::new(snd,title,fmt)?;
     self.wins.insert(id,ProgramWindow::Editor(win));
     Ok(())
   }